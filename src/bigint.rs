@@ -1,4 +1,4 @@
-use std::ops::{Add, AddAssign, Mul, MulAssign};
+use std::ops::{Add, AddAssign, Div, Mul, MulAssign, Rem, Sub, SubAssign};
 
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub struct BigUInt {
@@ -9,12 +9,14 @@ impl BigUInt {
     const MAX_DATA: u32 = 9999;
 
     pub fn new(n: u32) -> Self {
-        Self {
+        let mut result = Self {
             data: vec![n],
-        }
+        };
+        result.process_carry();
+        result
     }
 
-    pub fn digit_iter(&self) -> DigitIterator {
+    pub fn digit_iter(&self) -> DigitIterator<'_> {
         DigitIterator {
             big_uint: self,
             current_chunk: 0,
@@ -33,9 +35,86 @@ impl BigUInt {
                 carry = 0;
             }
         }
-        if carry > 0 {
-            self.data.push(carry);
+        while carry > 0 {
+            self.data.push(carry % (Self::MAX_DATA + 1));
+            carry /= Self::MAX_DATA + 1;
+        }
+        self.trim();
+    }
+
+    // 上位の0のチャンクを取り除く (0は[0]で表す)
+    fn trim(&mut self) {
+        while self.data.len() > 1 && *self.data.last().unwrap() == 0 {
+            self.data.pop();
+        }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.data.iter().all(|&chunk| chunk == 0)
+    }
+
+    pub fn is_even(&self) -> bool {
+        // 基数10000は偶数なので最下位のチャンクだけで決まる
+        self.data[0].is_multiple_of(2)
+    }
+
+    // 引き算 (self < rhsならNone)
+    pub fn checked_sub(&self, rhs: &Self) -> Option<Self> {
+        if self < rhs {
+            return None;
+        }
+        let mut data = self.data.clone();
+        let mut borrow = 0;
+        for (i, chunk) in data.iter_mut().enumerate() {
+            let sub = rhs.data.get(i).copied().unwrap_or(0) + borrow;
+            if *chunk >= sub {
+                *chunk -= sub;
+                borrow = 0;
+            } else {
+                *chunk += Self::MAX_DATA + 1 - sub;
+                borrow = 1;
+            }
         }
+        let mut result = Self { data };
+        result.trim();
+        Some(result)
+    }
+
+    // 0 <= x <= MAX_DATAとの掛け算
+    fn mul_small(&self, x: u32) -> Self {
+        let mut result = Self {
+            data: self.data.iter().map(|&chunk| chunk * x).collect(),
+        };
+        result.process_carry();
+        result
+    }
+
+    // 商と余り (rhsが0ならpanic)
+    // 筆算と同じく上のチャンクから順に、余りを一桁(基数10000)ずらしてチャンクを下ろし、
+    // 商のチャンクを二分探索で決める
+    pub fn div_rem(&self, rhs: &Self) -> (Self, Self) {
+        assert!(!rhs.is_zero(), "division by zero");
+        let mut quotient = vec![0; self.data.len()];
+        let mut remainder = Self::new(0);
+        for (i, &chunk) in self.data.iter().enumerate().rev() {
+            remainder.data.insert(0, chunk);
+            remainder.trim();
+            // rhs * q <= remainderとなる最大のq
+            let (mut lo, mut hi) = (0, Self::MAX_DATA);
+            while lo < hi {
+                let mid = (lo + hi).div_ceil(2);
+                if rhs.mul_small(mid) <= remainder {
+                    lo = mid;
+                } else {
+                    hi = mid - 1;
+                }
+            }
+            remainder = remainder.checked_sub(&rhs.mul_small(lo)).unwrap();
+            quotient[i] = lo;
+        }
+        let mut quotient = Self { data: quotient };
+        quotient.trim();
+        (quotient, remainder)
     }
 }
 
@@ -84,29 +163,60 @@ impl Mul for BigUInt {
     }
 }
 
-impl Ord for BigUInt {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.partial_cmp(other).unwrap()
+impl SubAssign for BigUInt {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = self.checked_sub(&rhs).expect("attempt to subtract with overflow");
     }
 }
 
-impl PartialOrd for BigUInt {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+impl Sub for BigUInt {
+    type Output = BigUInt;
+
+    fn sub(mut self, rhs: Self) -> Self::Output {
+        self -= rhs;
+        self
+    }
+}
+
+impl Div for BigUInt {
+    type Output = BigUInt;
+
+    fn div(self, rhs: Self) -> Self::Output {
+        self.div_rem(&rhs).0
+    }
+}
+
+impl Rem for BigUInt {
+    type Output = BigUInt;
+
+    fn rem(self, rhs: Self) -> Self::Output {
+        self.div_rem(&rhs).1
+    }
+}
+
+impl Ord for BigUInt {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
         if self.data.len() != other.data.len() {
-            return self.data.len().partial_cmp(&other.data.len());
+            return self.data.len().cmp(&other.data.len());
         }
         for (a, b) in self.data.iter().rev().zip(other.data.iter().rev()) {
             if *a != *b {
-                return a.partial_cmp(b);
+                return a.cmp(b);
             }
         }
-        Some(std::cmp::Ordering::Equal)
+        std::cmp::Ordering::Equal
+    }
+}
+
+impl PartialOrd for BigUInt {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
     }
 }
 
 impl From<u32> for BigUInt {
     fn from(x: u32) -> Self {
-        Self { data: vec![x] }
+        Self::new(x)
     }
 }
 
@@ -164,7 +274,7 @@ mod tests {
             (&[2, 2, 1], &[M0, M1], &[1, 1, 2]),
             (&[1, 2, 2, 1], &[M0, M1], &[0, 1, 3, 1]),
         ];
-        for (_i, &(a, b, expected)) in test_cases.iter().enumerate() {
+        for &(a, b, expected) in test_cases {
             let (mut a, b) = (BigUInt::from(a), BigUInt::from(b));
             a += b;
             assert_eq!(&a.data, expected);
@@ -193,6 +303,61 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_checked_sub() {
+        let test_cases: &[(&[u32], &[u32], &[u32])]  = &[
+            (&[0], &[0], &[0]),
+            (&[5], &[3], &[2]),
+            (&[0, 1], &[1], &[M0]),
+            (&[0, 0, 1], &[1], &[M0, M0]),
+            (&[1, 2, 3], &[1, 2, 3], &[0]),
+            (&[M1, M0, 1], &[M0, M0], &[M0, M0]),
+        ];
+        for &(a, b, expected) in test_cases {
+            let actual = BigUInt::from(a).checked_sub(&BigUInt::from(b)).unwrap();
+            assert_eq!(&actual.data, expected);
+        }
+        assert_eq!(BigUInt::from(3u32).checked_sub(&BigUInt::from(5u32)), None);
+        assert_eq!(BigUInt::from(&[M0][..]).checked_sub(&BigUInt::from(&[0, 1][..])), None);
+    }
+
+    #[test]
+    fn test_div_rem() {
+        // (a, b, aをbで割った商)
+        let test_cases: &[(&[u32], &[u32], &[u32])]  = &[
+            (&[0], &[7], &[0]),
+            (&[6], &[7], &[0]),
+            (&[2, 4, 6], &[2], &[1, 2, 3]),
+            (&[M0, M1, M1, 2], &[1, 2, 3], &[M0]),
+            (&[M0, M1, M1, 2], &[M0], &[1, 2, 3]),
+            (&[1, 0, 0, M1, M0, M0], &[M0, M0, M0], &[M0, M0, M0]),
+            (&[2, 0, 0, M1, M0, M0], &[M0, M0, M0], &[M0, M0, M0]),
+            (&[1, 2, 3], &[0, 0, 0, 1], &[0]),
+        ];
+        for &(a, b, expected) in test_cases {
+            let (a, b) = (BigUInt::from(a), BigUInt::from(b));
+            let (q, r) = a.div_rem(&b);
+            assert_eq!(&q.data, expected);
+            assert!(r < b);
+            assert_eq!(q * b + r, a);
+        }
+        // 2^100 = 1267650600228229401496703205376
+        let mut x = BigUInt::from(1u32);
+        for _ in 0..100 {
+            x *= BigUInt::from(2u32);
+        }
+        let (q, r) = x.div_rem(&BigUInt::from(1_000_000_007u32));
+        assert_eq!(r, BigUInt::from(976_371_285u32));
+        assert_eq!(q * BigUInt::from(1_000_000_007u32) + r, x);
+        assert_eq!(x.clone() / x.clone() - BigUInt::from(1u32), BigUInt::from(0u32));
+    }
+
+    #[test]
+    fn test_from_u32() {
+        assert_eq!(BigUInt::from(12_345_678u32).data, vec![5678, 1234]);
+        assert_eq!(BigUInt::from(u32::MAX).data, vec![7295, 9496, 42]);
+    }
+
     #[test]
     fn test_digit_iterator() {
         let test_cases: &[(&[u32], &[u32])]  = &[
@@ -9,11 +9,13 @@ fn main() {
 }
 
 
+#[allow(dead_code)]
 fn sum(m: i32, limit: i32) -> i32 {
     let n = (limit - 1) / m;
     n * (n + 1) / 2 * m
 }
 
+#[allow(dead_code)]
 fn another_solution() {
     const LIMIT: i32 = 1000;
     let answer = sum(3, LIMIT) + sum(5, LIMIT) - sum(15, LIMIT);
@@ -3,7 +3,7 @@ fn main() {
     let mut i = 7;
 
     loop {
-        while n % i == 0 {
+        while n.is_multiple_of(i) {
             n /= i;
         }
         if n == 1 {
@@ -29,11 +29,7 @@
 
 use project_euler::continued_fraction::*;
 use project_euler::bigint::BigUInt;
-
-
-fn is_square(n: u32) -> bool {
-    (n as f64).sqrt().floor().powi(2) as u32 == n
-}
+use project_euler::utils::is_square;
 
 
 fn solve_pells_equation(d: u32) -> (BigUInt, BigUInt) {
@@ -54,7 +50,7 @@ fn solve_pells_equation(d: u32) -> (BigUInt, BigUInt) {
 
 fn main() {
     let (answer, _) = (2..=1000)
-        .filter(|&d| !is_square(d))
+        .filter(|&d| !is_square(d as u64))
         .map(|d| (d, solve_pells_equation(d)))
         .max_by_key(|(_, (x, _))| x.clone())
         .unwrap();
@@ -71,7 +67,7 @@ mod tests {
         use super::BigUInt;
 
         for n in 2..=1000 {
-            if is_square(n) {
+            if is_square(n as u64) {
                 continue;
             }
             let (p, q) = solve_pells_equation(n);
@@ -0,0 +1,386 @@
+use crate::prime::factorize;
+use crate::utils::{crt, mod_inverse, modpow, mulmod};
+
+
+// 前計算する表の要素数の上限 (2^24、表一つで128MB)
+pub const MAX_TABLE_SIZE: u64 = 1 << 24;
+
+
+// 素数pを法とする二項係数
+// limit(とp - 1の小さい方)までの階乗とその逆元を前計算しておき、C(n, k)をO(1)で求める。
+// limit >= p - 1なら、n >= pのときもリュカの定理
+//   C(n, k) ≡ ∏C(n_i, k_i) (mod p) (n_i, k_iはn, kのp進数表示の各桁)
+// で求められる。
+pub struct Binomial {
+    p: u64,
+    fact: Vec<u64>,
+    inv_fact: Vec<u64>,
+}
+
+impl Binomial {
+    pub fn new(limit: usize, p: u64) -> Self {
+        assert!(p >= 2, "modulus must be a prime");
+        let len = (limit as u64 + 1).min(p) as usize;
+        let mut fact = vec![1 % p; len];
+        for i in 1..len {
+            fact[i] = mulmod(fact[i - 1], i as u64, p);
+        }
+        // フェルマーの小定理でlen - 1の階乗の逆元を求め、そこから順に下げていく
+        let mut inv_fact = vec![1 % p; len];
+        inv_fact[len - 1] = modpow(fact[len - 1], p - 2, p);
+        for i in (1..len).rev() {
+            inv_fact[i - 1] = mulmod(inv_fact[i], i as u64, p);
+        }
+        Self { p, fact, inv_fact }
+    }
+
+    pub fn modulus(&self) -> u64 {
+        self.p
+    }
+
+    // n! mod p
+    pub fn factorial(&self, n: u64) -> u64 {
+        self.fact[n as usize]
+    }
+
+    // (n!)^(-1) mod p
+    pub fn inv_factorial(&self, n: u64) -> u64 {
+        self.inv_fact[n as usize]
+    }
+
+    // C(n, k) mod p
+    pub fn binomial(&self, n: u64, k: u64) -> u64 {
+        if k > n {
+            return 0;
+        }
+        if n < self.fact.len() as u64 {
+            return self.binomial_small(n, k);
+        }
+        assert!(self.fact.len() as u64 == self.p, "n exceeds the precomputed limit");
+        lucas(n, k, self.p, |n_i, k_i| self.binomial_small(n_i, k_i))
+    }
+
+    fn binomial_small(&self, n: u64, k: u64) -> u64 {
+        let (n, k) = (n as usize, k as usize);
+        mulmod(mulmod(self.fact[n], self.inv_fact[k], self.p), self.inv_fact[n - k], self.p)
+    }
+}
+
+
+// リュカの定理でC(n, k) mod pを各桁の二項係数の積にする
+// binomial_digit(n_i, k_i)はk_i <= n_i < pについてC(n_i, k_i) mod pを返す
+fn lucas(mut n: u64, mut k: u64, p: u64, binomial_digit: impl Fn(u64, u64) -> u64) -> u64 {
+    let mut result = 1 % p;
+    while k > 0 {
+        let (n_i, k_i) = (n % p, k % p);
+        if k_i > n_i {
+            return 0;
+        }
+        result = mulmod(result, binomial_digit(n_i, k_i), p);
+        n /= p;
+        k /= p;
+    }
+    result
+}
+
+
+// 表を作らずにC(n, k) mod pを求める (pは素数)
+// 表が大きくなりすぎる大きな素数の法のためのもので、各桁はmin(k_i, n_i - k_i)項の積と逆元一回で求める。
+// そのため手間はkのp進数表示の各桁の大きさに比例する。
+pub fn binomial_mod_prime(n: u64, k: u64, p: u64) -> u64 {
+    assert!(p >= 2, "modulus must be a prime");
+    if k > n {
+        return 0;
+    }
+    lucas(n, k, p, |n_i, k_i| {
+        let k_i = k_i.min(n_i - k_i);
+        let mut numerator = 1 % p;
+        let mut denominator = 1 % p;
+        for j in 0..k_i {
+            numerator = mulmod(numerator, n_i - j, p);
+            denominator = mulmod(denominator, j + 1, p);
+        }
+        mulmod(numerator, modpow(denominator, p - 2, p), p)
+    })
+}
+
+
+// 素数冪p^eを法とする二項係数
+//
+// n!からpの因数を除いたものを(n!)_pとすると
+//   C(n, k) = p^c * (n!)_p / ((k!)_p * ((n - k)!)_p)
+// で、cはn - kとkをp進数で足したときの繰り上がりの回数(クンマーの定理)。
+// (n!)_pはp^eと互いに素な数の積なので逆元がとれる。さらに
+//   (n!)_p ≡ F(p^e)^⌊n/p^e⌋ * F(n mod p^e) * (⌊n/p⌋!)_p (mod p^e)
+// (F(x)はx以下でpと互いに素な数の積)と再帰的に計算できるので、Fの表を前計算しておく。
+// F(p^e)はウィルソンの定理の一般化により-1 (p = 2, e >= 3のときだけ1)なので、
+// 表はlimitとp^eの小さい方まであればよい。表がMAX_TABLE_SIZEを超えるときはNone。
+pub struct BinomialPrimePower {
+    p: u64,
+    e: u32,
+    pe: u64,
+    full: u64,              // F(p^e) mod p^e
+    table: Vec<u64>,
+}
+
+impl BinomialPrimePower {
+    pub fn new(limit: u64, p: u64, e: u32) -> Option<Self> {
+        let pe = p.checked_pow(e).expect("prime power modulus overflows u64");
+        let len = limit.min(pe);
+        if len > MAX_TABLE_SIZE {
+            return None;
+        }
+        let mut table = vec![1 % pe; len as usize + 1];
+        for i in 1..=len as usize {
+            table[i] = if (i as u64).is_multiple_of(p) { table[i - 1] } else { mulmod(table[i - 1], i as u64, pe) };
+        }
+        let full = if p == 2 && e >= 3 { 1 } else { pe - 1 };
+        Some(Self { p, e, pe, full, table })
+    }
+
+    pub fn modulus(&self) -> u64 {
+        self.pe
+    }
+
+    // (n!)_p mod p^e
+    fn factorial_without_p(&self, n: u64) -> u64 {
+        let mut result = 1 % self.pe;
+        let mut n = n;
+        while n > 0 {
+            let full = modpow(self.full, n / self.pe, self.pe);
+            result = mulmod(result, mulmod(full, self.table[(n % self.pe) as usize], self.pe), self.pe);
+            n /= self.p;
+        }
+        result
+    }
+
+    // C(n, k) mod p^e
+    pub fn binomial(&self, n: u64, k: u64) -> u64 {
+        if k > n {
+            return 0;
+        }
+        assert!(n < self.table.len() as u64 || self.table.len() as u64 > self.pe, "n exceeds the precomputed limit");
+        // v_p(C(n, k)) = v_p(n!) - v_p(k!) - v_p((n - k)!)
+        let v_p = |mut x: u64| {
+            let mut count = 0;
+            while x > 0 {
+                x /= self.p;
+                count += x;
+            }
+            count
+        };
+        let c = v_p(n) - v_p(k) - v_p(n - k);
+        if c >= self.e as u64 {
+            return 0;
+        }
+        let denominator = mulmod(self.factorial_without_p(k), self.factorial_without_p(n - k), self.pe);
+        let unit = mulmod(self.factorial_without_p(n), mod_inverse(denominator, self.pe).unwrap(), self.pe);
+        mulmod(unit, self.p.pow(c as u32), self.pe)
+    }
+}
+
+
+// 合成数mを法とする二項係数
+// mを素因数分解して素数冪ごとに求め、中国剰余定理で合わせる
+// 素数の因数pには表が小さければBinomial(limitとp - 1の小さい方まで)を使い、
+// 大きければbinomial_mod_primeで毎回計算する。素数冪の因数にはBinomialPrimePowerを使う。
+// nはlimit以下であること (ただし素数の因数についてはリュカの定理でいくらでも大きくてよい)
+// 素数冪の因数の表がMAX_TABLE_SIZEを超えるときはNone
+pub struct BinomialMod {
+    m: u64,
+    parts: Vec<BinomialModPart>,
+}
+
+enum BinomialModPart {
+    Prime(Binomial),
+    LargePrime(u64),
+    PrimePower(BinomialPrimePower),
+}
+
+impl BinomialModPart {
+    fn modulus(&self) -> u64 {
+        match self {
+            BinomialModPart::Prime(binomial) => binomial.modulus(),
+            BinomialModPart::LargePrime(p) => *p,
+            BinomialModPart::PrimePower(binomial) => binomial.modulus(),
+        }
+    }
+
+    fn binomial(&self, n: u64, k: u64) -> u64 {
+        match self {
+            BinomialModPart::Prime(binomial) => binomial.binomial(n, k),
+            BinomialModPart::LargePrime(p) => binomial_mod_prime(n, k, *p),
+            BinomialModPart::PrimePower(binomial) => binomial.binomial(n, k),
+        }
+    }
+}
+
+impl BinomialMod {
+    pub fn new(limit: u64, m: u64) -> Option<Self> {
+        assert!(m > 0, "modulus must be positive");
+        // m = 1なら因数がなく、binomialは常に0を返す
+        let parts = factorize(m).into_iter()
+            .map(|(p, e)| match e {
+                1 if limit.min(p - 1) < MAX_TABLE_SIZE => Some(BinomialModPart::Prime(Binomial::new(limit.min(p - 1) as usize, p))),
+                1 => Some(BinomialModPart::LargePrime(p)),
+                _ => BinomialPrimePower::new(limit, p, e).map(BinomialModPart::PrimePower),
+            })
+            .collect::<Option<Vec<_>>>()?;
+        Some(Self { m, parts })
+    }
+
+    pub fn modulus(&self) -> u64 {
+        self.m
+    }
+
+    pub fn binomial(&self, n: u64, k: u64) -> u64 {
+        let congruences = self.parts.iter()
+            .map(|part| (part.binomial(n, k), part.modulus()))
+            .collect::<Vec<_>>();
+        crt(&congruences).unwrap().0
+    }
+}
+
+
+// C(n, k) mod m
+// 同じmで何度も求めるときはBinomialModを使う
+// mの素数冪の因数p^e (e >= 2)がMAX_TABLE_SIZEとnのどちらよりも大きいときはNone
+pub fn binomial_mod(n: u64, k: u64, m: u64) -> Option<u64> {
+    Some(BinomialMod::new(n, m)?.binomial(n, k))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // パスカルの三角形
+    fn pascal(limit: usize, m: u64) -> Vec<Vec<u64>> {
+        let mut rows = vec![vec![1 % m]];
+        for n in 1..=limit {
+            let prev = &rows[n - 1];
+            let mut row = vec![1 % m; n + 1];
+            for k in 1..n {
+                row[k] = (prev[k - 1] + prev[k]) % m;
+            }
+            rows.push(row);
+        }
+        rows
+    }
+
+    #[test]
+    fn test_binomial() {
+        let p = 1_000_000_007;
+        let binomial = Binomial::new(1000, p);
+        let rows = pascal(200, p);
+        for (n, row) in rows.iter().enumerate() {
+            for (k, &expected) in row.iter().enumerate() {
+                assert_eq!(binomial.binomial(n as u64, k as u64), expected);
+            }
+            assert_eq!(binomial.binomial(n as u64, n as u64 + 1), 0);
+        }
+        assert_eq!(binomial.factorial(10), 3_628_800);
+        assert_eq!(mulmod(binomial.factorial(1000), binomial.inv_factorial(1000), p), 1);
+        // C(1000, 500) mod 10^9 + 7
+        assert_eq!(binomial.binomial(1000, 500), 159_835_829);
+    }
+
+    #[test]
+    fn test_lucas() {
+        for &p in &[2, 3, 5, 7, 13] {
+            let binomial = Binomial::new(p as usize, p);
+            let rows = pascal(300, p);
+            for (n, row) in rows.iter().enumerate() {
+                for (k, &expected) in row.iter().enumerate() {
+                    assert_eq!(binomial.binomial(n as u64, k as u64), expected, "C({}, {}) mod {}", n, k, p);
+                }
+            }
+        }
+        let binomial = Binomial::new(1_000_002, 1_000_003);
+        assert_eq!(binomial.binomial(10u64.pow(18), 5 * 10u64.pow(17)), 675_584);
+        assert_eq!(binomial.binomial(10u64.pow(18), 123_456_789), 0);
+    }
+
+    #[test]
+    fn test_binomial_prime_power() {
+        for &(p, e) in &[(2, 1), (2, 3), (2, 10), (3, 1), (3, 4), (5, 3), (7, 2), (11, 2)] {
+            let binomial = BinomialPrimePower::new(200, p, e).unwrap();
+            let m = p.pow(e);
+            let rows = pascal(200, m);
+            for (n, row) in rows.iter().enumerate() {
+                for (k, &expected) in row.iter().enumerate() {
+                    assert_eq!(binomial.binomial(n as u64, k as u64), expected, "C({}, {}) mod {}^{}", n, k, p, e);
+                }
+            }
+        }
+        assert_eq!(BinomialPrimePower::new(100_000, 2, 20).unwrap().binomial(100_000, 50_000), 387_648);
+        assert_eq!(BinomialPrimePower::new(100_000, 3, 12).unwrap().binomial(100_000, 50_000), 373_977);
+
+        // nがp^eより小さければ表はnまででよい
+        let binomial = BinomialPrimePower::new(50, 7, 3).unwrap();
+        let rows = pascal(50, 343);
+        for (n, row) in rows.iter().enumerate() {
+            for (k, &expected) in row.iter().enumerate() {
+                assert_eq!(binomial.binomial(n as u64, k as u64), expected);
+            }
+        }
+        assert!(std::panic::catch_unwind(|| binomial.binomial(51, 3)).is_err());
+        // 表がMAX_TABLE_SIZEを超える
+        assert!(BinomialPrimePower::new(u64::MAX, 4099, 2).is_none());
+        assert!(BinomialPrimePower::new(MAX_TABLE_SIZE, 4099, 2).is_some());
+    }
+
+    #[test]
+    fn test_binomial_mod_prime() {
+        for &p in &[2, 3, 5, 7, 13, 1_000_000_007] {
+            let rows = pascal(100, p);
+            for (n, row) in rows.iter().enumerate() {
+                for (k, &expected) in row.iter().enumerate() {
+                    assert_eq!(binomial_mod_prime(n as u64, k as u64, p), expected, "C({}, {}) mod {}", n, k, p);
+                }
+            }
+        }
+        let p = 1_000_000_007;
+        let n = 1_000_000_000_499_999_951;         // p進数で[500_000_000, 999_999_993]
+        assert_eq!(binomial_mod_prime(10u64.pow(18), 5, p), 1_906_884);
+        assert_eq!(binomial_mod_prime(n, 123_456, p), 837_505_172);
+        assert_eq!(binomial_mod_prime(n, 12 * p + 123_456, p), 115_464_654);
+        assert_eq!(binomial_mod_prime(n, n - 5, p), 27_343_715);
+        assert_eq!(binomial_mod_prime(n, 500_000_001, p), 0);
+    }
+
+    #[test]
+    fn test_binomial_mod() {
+        for m in 1..=60 {
+            let binomial = BinomialMod::new(80, m).unwrap();
+            let rows = pascal(80, m);
+            for (n, row) in rows.iter().enumerate() {
+                for (k, &expected) in row.iter().enumerate() {
+                    assert_eq!(binomial.binomial(n as u64, k as u64), expected, "C({}, {}) mod {}", n, k, m);
+                }
+            }
+        }
+        assert_eq!(binomial_mod(1000, 300, 142_857), Some(98901));
+        assert_eq!(binomial_mod(100_000, 50_000, 1_000_000_000), Some(891_416_640));
+        assert_eq!(binomial_mod(5, 7, 10), Some(0));
+        assert_eq!(binomial_mod(10, 3, 1), Some(0));
+        assert_eq!(binomial_mod(10u64.pow(18), 5, 1), Some(0));
+
+        // 大きな素数の法では表をnまでしか作らない
+        assert_eq!(binomial_mod(10, 3, 1_000_000_007), Some(120));
+        assert_eq!(binomial_mod(1000, 500, 1_000_000_007), Some(159_835_829));
+        assert_eq!(binomial_mod(1000, 500, 2 * 1_000_000_007), Some(159_835_829 + 1_000_000_007));
+        assert_eq!(binomial_mod(100_000, 50_000, 18_446_744_073_709_551_557), Some(8_247_819_629_397_927_032));
+        let binomial = BinomialMod::new(10_000, 998_244_353 * 4).unwrap();
+        assert_eq!(binomial.binomial(10_000, 1234), 1_614_598_536);
+
+        // nも法も大きければ表を作らずに求める
+        assert_eq!(binomial_mod(10u64.pow(18), 5, 1_000_000_007), Some(1_906_884));
+        assert_eq!(binomial_mod(10u64.pow(18), 5, 2 * 1_000_000_007), Some(1_906_884));
+        assert_eq!(binomial_mod(10u64.pow(18), 10u64.pow(18) - 7, 1_000_000_007 * 998_244_353), Some(546_570_017_911_890_682));
+
+        // 素数冪の因数の表はnとp^eの小さい方まで
+        assert_eq!(binomial_mod(100_000, 50_000, 4099 * 4099), Some(2_624_751));
+        assert_eq!(binomial_mod(10u64.pow(18), 5, 4099 * 4099), None);
+    }
+}
@@ -0,0 +1,337 @@
+use crate::prime::{is_prime, PseudoPrimeIterator};
+use crate::utils::{gcd, modpow};
+use std::fmt;
+use std::str::FromStr;
+
+
+// 素数性の証明書
+//
+// Pratt:
+//   n - 1 = ∏q_i^e_iと完全に素因数分解でき、
+//   a^(n-1) ≡ 1 かつ すべてのq_iについて a^((n-1)/q_i) ≢ 1 (mod n)
+//   となるaがあればaの位数はn - 1なのでnは素数。
+// Pocklington:
+//   n - 1 = F * R (F = ∏q_i^e_i > √n) と部分的に素因数分解でき、
+//   各q_iについて a_i^(n-1) ≡ 1 かつ gcd(a_i^((n-1)/q_i) - 1, n) = 1
+//   となるa_iがあればnの任意の素因数pについてp ≡ 1 (mod F)、つまりp > √nなのでnは素数。
+//
+// いずれもq_iの素数性は再帰的に証明書で示す。
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum Certificate {
+    Two,
+    Pratt {
+        n: u64,
+        witness: u64,
+        factors: Vec<(Certificate, u32)>,
+    },
+    Pocklington {
+        n: u64,
+        factors: Vec<(Certificate, u32, u64)>,      // (q_i, e_i, a_i)
+    },
+}
+
+impl Certificate {
+    pub fn n(&self) -> u64 {
+        match self {
+            Certificate::Two => 2,
+            Certificate::Pratt { n, .. } | Certificate::Pocklington { n, .. } => *n,
+        }
+    }
+
+    // 証明書が正しいか確かめる (modpowとgcdだけを使う)
+    pub fn verify(&self) -> bool {
+        match self {
+            Certificate::Two => true,
+            Certificate::Pratt { n, witness, factors } => {
+                let n = *n;
+                if n < 3 || !factors.iter().all(|(q, _)| q.verify()) {
+                    return false;
+                }
+                if factorized_part(factors.iter().map(|(q, e)| (q.n(), *e))) != Some(n - 1) {
+                    return false;
+                }
+                modpow(*witness, n - 1, n) == 1
+                    && factors.iter().all(|(q, _)| modpow(*witness, (n - 1) / q.n(), n) != 1)
+            }
+            Certificate::Pocklington { n, factors } => {
+                let n = *n;
+                if n < 3 || !factors.iter().all(|(q, _, _)| q.verify()) {
+                    return false;
+                }
+                let f = match factorized_part(factors.iter().map(|(q, e, _)| (q.n(), *e))) {
+                    Some(f) if (n - 1) % f == 0 => f,
+                    _ => return false,
+                };
+                if (f as u128) * (f as u128) <= n as u128 {
+                    return false;
+                }
+                factors.iter().all(|(q, _, a)| {
+                    // x - 1 mod n (x + n - 1はnが2^64に近いと溢れる)
+                    let x = modpow(*a, (n - 1) / q.n(), n);
+                    let x_minus_1 = if x == 0 { n - 1 } else { x - 1 };
+                    modpow(*a, n - 1, n) == 1 && gcd(x_minus_1, n) == 1
+                })
+            }
+        }
+    }
+}
+
+
+// ∏q_i^e_i (オーバーフローしたらNone)
+fn factorized_part(factors: impl Iterator<Item = (u64, u32)>) -> Option<u64> {
+    let mut product: u64 = 1;
+    for (q, e) in factors {
+        product = product.checked_mul(q.checked_pow(e)?)?;
+    }
+    Some(product)
+}
+
+
+// nが素数ならその証明書を作る
+// n - 1の素因数が√nを超えるまで見つかればPocklington、完全に分解しきったらPrattを使う
+pub fn certify(n: u64) -> Option<Certificate> {
+    if !is_prime(n) {
+        return None;
+    }
+    if n == 2 {
+        return Some(Certificate::Two);
+    }
+
+    let (factors, complete) = partial_factorize(n - 1, n);
+    if complete {
+        let witness = (2..n).find(|&a| {
+            modpow(a, n - 1, n) == 1 && factors.iter().all(|&(q, _)| modpow(a, (n - 1) / q, n) != 1)
+        })?;
+        let factors = factors.iter()
+            .map(|&(q, e)| Some((certify(q)?, e)))
+            .collect::<Option<Vec<_>>>()?;
+        Some(Certificate::Pratt { n, witness, factors })
+    } else {
+        let factors = factors.iter()
+            .map(|&(q, e)| {
+                let a = (2..n).find(|&a| modpow(a, (n - 1) / q, n) != 1)?;
+                Some((certify(q)?, e, a))
+            })
+            .collect::<Option<Vec<_>>>()?;
+        Some(Certificate::Pocklington { n, factors })
+    }
+}
+
+
+// mを素因数の小さい順に分解し、分解済みの部分Fが√nを超えた時点で止める
+// 戻り値の二つ目はmを完全に分解できたかどうか
+fn partial_factorize(mut m: u64, n: u64) -> (Vec<(u64, u32)>, bool) {
+    let mut factors = Vec::new();
+    let mut f: u128 = 1;
+    let divisors = [2, 3].iter().copied().chain(PseudoPrimeIterator::new());
+    // 残りの素数判定はmが変わったときだけやり直す
+    let mut m_changed = true;
+    for p in divisors {
+        if m == 1 {
+            return (factors, true);
+        }
+        if (m_changed && is_prime(m)) || p > m / p {
+            factors.push((m, 1));
+            return (factors, true);
+        }
+        m_changed = false;
+        let mut e = 0;
+        while m.is_multiple_of(p) {
+            m /= p;
+            f *= p as u128;
+            e += 1;
+        }
+        if e > 0 {
+            m_changed = true;
+            factors.push((p, e));
+            if f * f > n as u128 {
+                return (factors, m == 1);
+            }
+        }
+    }
+    unreachable!()
+}
+
+
+// テキスト形式
+// 1行に1つの証明書を書き、その後に各因数q_iの証明書を順に続ける
+//   two
+//   pratt <n> <witness> <q_1>^<e_1> <q_2>^<e_2> ...
+//   pocklington <n> <q_1>^<e_1>@<a_1> <q_2>^<e_2>@<a_2> ...
+impl fmt::Display for Certificate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Certificate::Two => writeln!(f, "two"),
+            Certificate::Pratt { n, witness, factors } => {
+                write!(f, "pratt {} {}", n, witness)?;
+                for (q, e) in factors {
+                    write!(f, " {}^{}", q.n(), e)?;
+                }
+                writeln!(f)?;
+                factors.iter().try_for_each(|(q, _)| write!(f, "{}", q))
+            }
+            Certificate::Pocklington { n, factors } => {
+                write!(f, "pocklington {}", n)?;
+                for (q, e, a) in factors {
+                    write!(f, " {}^{}@{}", q.n(), e, a)?;
+                }
+                writeln!(f)?;
+                factors.iter().try_for_each(|(q, _, _)| write!(f, "{}", q))
+            }
+        }
+    }
+}
+
+impl FromStr for Certificate {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut lines = s.lines().filter(|line| !line.trim().is_empty());
+        let certificate = parse_certificate(&mut lines)?;
+        match lines.next() {
+            Some(line) => Err(format!("unexpected line: {}", line)),
+            None => Ok(certificate),
+        }
+    }
+}
+
+fn parse_certificate<'a>(lines: &mut impl Iterator<Item = &'a str>) -> Result<Certificate, String> {
+    let line = lines.next().ok_or("unexpected end of input")?;
+    let mut tokens = line.split_whitespace();
+    let parse_u64 = |s: Option<&str>| -> Result<u64, String> {
+        let s = s.ok_or_else(|| format!("missing number: {}", line))?;
+        s.parse().map_err(|_| format!("invalid number {:?}: {}", s, line))
+    };
+    // "q^e"
+    let parse_factor = |s: &str| -> Result<(u64, u32), String> {
+        let (q, e) = s.split_once('^').ok_or_else(|| format!("invalid factor {:?}: {}", s, line))?;
+        let q = parse_u64(Some(q))?;
+        let e = e.parse().map_err(|_| format!("invalid exponent {:?}: {}", e, line))?;
+        Ok((q, e))
+    };
+    // 子の証明書を読み、その証明する数が期待通りか確かめる
+    let mut parse_child = |q: u64| -> Result<Certificate, String> {
+        let child = parse_certificate(lines)?;
+        if child.n() != q {
+            return Err(format!("expected certificate for {}, found {}", q, child.n()));
+        }
+        Ok(child)
+    };
+
+    match tokens.next() {
+        Some("two") => Ok(Certificate::Two),
+        Some("pratt") => {
+            let n = parse_u64(tokens.next())?;
+            let witness = parse_u64(tokens.next())?;
+            let factors = tokens.map(parse_factor).collect::<Result<Vec<_>, _>>()?;
+            let factors = factors.into_iter()
+                .map(|(q, e)| Ok((parse_child(q)?, e)))
+                .collect::<Result<Vec<_>, String>>()?;
+            Ok(Certificate::Pratt { n, witness, factors })
+        }
+        Some("pocklington") => {
+            let n = parse_u64(tokens.next())?;
+            let factors = tokens
+                .map(|s| {
+                    let (factor, a) = s.split_once('@').ok_or_else(|| format!("missing witness {:?}: {}", s, line))?;
+                    let (q, e) = parse_factor(factor)?;
+                    Ok((q, e, parse_u64(Some(a))?))
+                })
+                .collect::<Result<Vec<_>, String>>()?;
+            let factors = factors.into_iter()
+                .map(|(q, e, a)| Ok((parse_child(q)?, e, a)))
+                .collect::<Result<Vec<_>, String>>()?;
+            Ok(Certificate::Pocklington { n, factors })
+        }
+        _ => Err(format!("unknown certificate: {}", line)),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_certify() {
+        for n in 0..=20000 {
+            match certify(n) {
+                Some(certificate) => {
+                    assert!(is_prime(n));
+                    assert_eq!(certificate.n(), n);
+                    assert!(certificate.verify());
+                }
+                None => assert!(!is_prime(n)),
+            }
+        }
+        let large_primes = &[
+            1_000_000_007,
+            1_000_000_000_039,
+            (1 << 61) - 1,
+            200_000_000_000_000_363,                // 安全素数 (2 * 100000000000000181 + 1)
+            18_446_744_073_709_551_557,
+            18_446_744_073_709_551_533,             // Pocklingtonの検証でx + n - 1が溢れる
+            9_007_376_153_745_587,                  // 2 * 67108879 * 67110167 + 1
+        ];
+        for &n in large_primes {
+            let certificate = certify(n).unwrap();
+            assert!(certificate.verify());
+            assert!(certificate.to_string().parse::<Certificate>().unwrap().verify());
+        }
+    }
+
+    #[test]
+    fn test_kinds() {
+        // 1000000007 - 1 = 2 * 500000003
+        match certify(1_000_000_007).unwrap() {
+            Certificate::Pratt { factors, .. } => assert_eq!(factors.len(), 2),
+            certificate => panic!("{:?}", certificate),
+        }
+        // 2^61 - 2 = 2 * 3^2 * 5^2 * 7 * 11 * 13 * 31 * 41 * 61 * ...で小さい因数だけで√nを超える
+        match certify((1 << 61) - 1).unwrap() {
+            Certificate::Pocklington { .. } => {}
+            certificate => panic!("{:?}", certificate),
+        }
+    }
+
+    #[test]
+    fn test_verify_rejects_invalid() {
+        // 561 = 3 * 11 * 17はカーマイケル数
+        let fake = Certificate::Pratt {
+            n: 561,
+            witness: 2,
+            factors: vec![(Certificate::Two, 4), (certify(5).unwrap(), 1), (certify(7).unwrap(), 1)],
+        };
+        assert!(!fake.verify());
+
+        let valid = certify(1_000_000_007).unwrap();
+        if let Certificate::Pratt { n, factors, .. } = valid.clone() {
+            // 位数がn - 1にならない底
+            assert!(!Certificate::Pratt { n, witness: 4, factors: factors.clone() }.verify());
+            // 因数が足りない
+            assert!(!Certificate::Pratt { n, witness: 5, factors: factors[..1].to_vec() }.verify());
+        }
+
+        // Fが√n以下
+        let small_f = Certificate::Pocklington { n: 1_000_000_007, factors: vec![(Certificate::Two, 1, 3)] };
+        assert!(!small_f.verify());
+    }
+
+    #[test]
+    fn test_text_format() {
+        for &n in &[2, 3, 97, 1_000_000_007, (1 << 61) - 1, 18_446_744_073_709_551_557] {
+            let certificate = certify(n).unwrap();
+            let text = certificate.to_string();
+            assert_eq!(text.parse::<Certificate>(), Ok(certificate));
+        }
+        let text = "pratt 7 3 2^1 3^1\ntwo\npratt 3 2 2^1\ntwo\n";
+        let certificate = text.parse::<Certificate>().unwrap();
+        assert!(certificate.verify());
+        assert_eq!(certificate.to_string(), text);
+
+        assert!("pratt 7 3 2^1 3^1\ntwo\n".parse::<Certificate>().is_err());
+        assert!("pratt 7 3 2^1 3^1\ntwo\npratt 5 2 2^2\ntwo\n".parse::<Certificate>().is_err());
+        assert!("two\ntwo\n".parse::<Certificate>().is_err());
+        assert!("prime 7".parse::<Certificate>().is_err());
+    }
+}
@@ -1,4 +1,5 @@
 use crate::bigint::BigUInt;
+use crate::utils::isqrt;
 
 
 type Term = (i64, (i64, i64));      //  a * (√n + b)
@@ -32,7 +33,7 @@ fn convert_to_mixed_frac((num, denom): (Term, i64)) -> (i64, (Term, i64)) {
     let n = num.1.0;
     let b = num.1.1;
 
-    let floor_sqrt_n = (n as f64).sqrt() as i64;
+    let floor_sqrt_n = isqrt(n as u64) as i64;
     let (int, new_b) = {
         let mut expelled_num = a * (b + floor_sqrt_n);
         let mut new_b = -floor_sqrt_n;
@@ -48,7 +49,7 @@ fn convert_to_mixed_frac((num, denom): (Term, i64)) -> (i64, (Term, i64)) {
 
 
 pub fn sqrt_into_continued_frac(n: u32) -> (u32, Vec<u32>) {
-    let floor_sqrt_n = (n as f64).sqrt() as i64;
+    let floor_sqrt_n = isqrt(n as u64) as i64;
     let a_0 = floor_sqrt_n;                     // 途中から循環するケースが
     let mut period = Vec::new();    // ないと仮定している
 
@@ -70,7 +71,7 @@ pub fn sqrt_into_continued_frac(n: u32) -> (u32, Vec<u32>) {
 }
 
 
-pub fn calc_convergents(a_0: u32, period: &Vec<u32>, n: u32) -> Vec<(BigUInt, BigUInt)> {
+pub fn calc_convergents(a_0: u32, period: &[u32], n: u32) -> Vec<(BigUInt, BigUInt)> {
     let mut result = vec![(BigUInt::from(a_0), BigUInt::from(1u32))];
     let mut old_denoms = Vec::new();
 
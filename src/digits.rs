@@ -0,0 +1,229 @@
+use std::convert::TryFrom;
+use std::ops::RangeInclusive;
+
+
+// nのbase進数表示の各桁 (BigUInt::digit_iterと同じく下の桁から順に並べる)
+// 0は[0]
+pub fn digits(n: u64, base: u64) -> Vec<u64> {
+    assert!(base >= 2, "base must be at least 2");
+    let mut result = Vec::new();
+    let mut n = n;
+    loop {
+        result.push(n % base);
+        n /= base;
+        if n == 0 {
+            break;
+        }
+    }
+    result
+}
+
+
+// digitsの逆 (下の桁から順に並んだ各桁から数を作る)
+// u64に収まらなければNone
+pub fn from_digits(digits: &[u64], base: u64) -> Option<u64> {
+    digits.iter().rev().try_fold(0u64, |acc, &d| acc.checked_mul(base)?.checked_add(d))
+}
+
+
+// 桁を逆順にした数 (1230 -> 321)
+// 10000000000000000002 -> 20000000000000000001のようにu64に収まらなければNone
+pub fn reverse_number(n: u64, base: u64) -> Option<u64> {
+    let mut reversed: u64 = 0;
+    let mut n = n;
+    while n != 0 {
+        reversed = reversed.checked_mul(base)?.checked_add(n % base)?;
+        n /= base;
+    }
+    Some(reversed)
+}
+
+
+pub fn digit_sum(n: u64, base: u64) -> u64 {
+    digits(n, base).iter().sum()
+}
+
+
+// 桁数 (0は1桁)
+pub fn digit_count(n: u64, base: u64) -> u32 {
+    digits(n, base).len() as u32
+}
+
+
+pub fn is_palindrome_in_base(n: u64, base: u64) -> bool {
+    let digits = digits(n, base);
+    digits.iter().eq(digits.iter().rev())
+}
+
+
+// 先頭の桁を末尾に回していったもの (n自身から始めて桁数個)
+// 197 -> [197, 971, 719]
+// どれかがu64に収まらなければNone
+pub fn rotations(n: u64, base: u64) -> Option<Vec<u64>> {
+    let count = digit_count(n, base);
+    let high = base.pow(count - 1);
+    let mut result = Vec::with_capacity(count as usize);
+    let mut x = n;
+    for i in 0..count {
+        result.push(x);
+        if i + 1 < count {
+            let next = (x % high) as u128 * base as u128 + (x / high) as u128;
+            x = u64::try_from(next).ok()?;
+        }
+    }
+    Some(result)
+}
+
+
+// 左右から桁を取り除いていったもの (n自身、左から取り除いたもの、右から取り除いたものの順)
+// 3797 -> [3797, 797, 97, 7, 379, 37, 3]
+pub fn truncations(n: u64, base: u64) -> Vec<u64> {
+    let count = digit_count(n, base);
+    let mut result = vec![n];
+    let mut high = base.pow(count - 1);
+    while high > 1 {
+        result.push(n % high);
+        high /= base;
+    }
+    let mut x = n / base;
+    while x != 0 {
+        result.push(x);
+        x /= base;
+    }
+    result
+}
+
+
+// 10進数表示にrangeの数字がちょうど一回ずつ現れ、それ以外は現れないか
+// 1から9の数字すべてならis_pandigital(n, 1..=9)
+pub fn is_pandigital(n: u64, range: RangeInclusive<u64>) -> bool {
+    let mut seen = [false; 10];
+    for d in digits(n, 10) {
+        if !range.contains(&d) || seen[d as usize] {
+            return false;
+        }
+        seen[d as usize] = true;
+    }
+    range.into_iter().all(|d| d < 10 && seen[d as usize])
+}
+
+
+// 10進数で並べてつなげた数 (12, 345 -> 12345)
+// u64に収まらなければNone
+pub fn concat(a: u64, b: u64) -> Option<u64> {
+    // bが20桁だと10^20がu64に収まらないのでu128で計算する
+    let shifted = (a as u128).checked_mul(10u128.pow(digit_count(b, 10)))?;
+    u64::try_from(shifted.checked_add(b as u128)?).ok()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prime::is_prime;
+
+    #[test]
+    fn test_digits() {
+        assert_eq!(digits(0, 10), vec![0]);
+        assert_eq!(digits(1230, 10), vec![0, 3, 2, 1]);
+        assert_eq!(digits(585, 2), vec![1, 0, 0, 1, 0, 0, 1, 0, 0, 1]);
+        assert_eq!(digits(u64::MAX, 16), vec![15; 16]);
+        for &base in &[2, 3, 10, 16, 1000] {
+            for n in (0..100_000).step_by(7) {
+                assert_eq!(from_digits(&digits(n, base), base), Some(n));
+            }
+        }
+        assert_eq!(from_digits(&[], 10), Some(0));
+        assert_eq!(from_digits(&digits(u64::MAX, 10), 10), Some(u64::MAX));
+        assert_eq!(from_digits(&[6, 1, 6, 1, 5, 5, 9, 0, 7, 3, 7, 0, 4, 4, 7, 6, 4, 4, 8, 1], 10), None);
+        assert_eq!(from_digits(&[9; 25], 10), None);
+        assert_eq!(from_digits(&[1; 65], 2), None);
+        assert_eq!(digit_sum(2u64.pow(15), 10), 26);
+        assert_eq!(digit_sum(255, 2), 8);
+        assert_eq!(digit_count(0, 10), 1);
+        assert_eq!(digit_count(999, 10), 3);
+        assert_eq!(digit_count(1000, 10), 4);
+        assert_eq!(digit_count(u64::MAX, 10), 20);
+        assert_eq!(digit_count(u64::MAX, 2), 64);
+    }
+
+    #[test]
+    fn test_reverse_and_palindrome() {
+        assert_eq!(reverse_number(1230, 10), Some(321));
+        assert_eq!(reverse_number(0, 10), Some(0));
+        assert_eq!(reverse_number(0b1011, 2), Some(0b1101));
+        assert_eq!(reverse_number(10_000_000_000_000_000_001, 10), Some(10_000_000_000_000_000_001));
+        assert_eq!(reverse_number(10_000_000_000_000_000_002, 10), None);
+        assert_eq!(reverse_number(u64::MAX, 2), Some(u64::MAX));
+        assert!(is_palindrome_in_base(0, 10));
+        assert!(is_palindrome_in_base(906_609, 10));
+        assert!(!is_palindrome_in_base(10, 10));
+        // 逆順にした数がu64に収まらなくても判定できる
+        assert!(!is_palindrome_in_base(10_000_000_000_000_000_002, 10));
+        assert!(!is_palindrome_in_base(3u64.pow(40) + 2, 3));
+        assert!(is_palindrome_in_base(3u64.pow(40) + 1, 3));
+        assert!(is_palindrome_in_base(u64::MAX, 2));
+        // 10進数でも2進数でも回文になる100万未満の数の和
+        let sum = (1..1_000_000)
+            .filter(|&n| is_palindrome_in_base(n, 10) && is_palindrome_in_base(n, 2))
+            .sum::<u64>();
+        assert_eq!(sum, 872_187);
+    }
+
+    #[test]
+    fn test_rotations_and_truncations() {
+        assert_eq!(rotations(197, 10), Some(vec![197, 971, 719]));
+        assert_eq!(rotations(7, 10), Some(vec![7]));
+        assert_eq!(rotations(0, 10), Some(vec![0]));
+        assert_eq!(rotations(101, 10), Some(vec![101, 11, 110]));
+        assert_eq!(rotations(0b110, 2), Some(vec![0b110, 0b101, 0b011]));
+        assert_eq!(rotations(u64::MAX, 2), Some(vec![u64::MAX; 64]));
+        // 18446744073709551615 -> 84467440737095516151 はu64に収まらない
+        assert_eq!(rotations(u64::MAX, 10), None);
+        assert_eq!(rotations(10_000_000_000_000_000_000, 10).map(|r| r.len()), Some(20));
+        // 100未満の巡回素数は13個
+        let circular = (2..100).filter(|&n| rotations(n, 10).unwrap().into_iter().all(is_prime)).count();
+        assert_eq!(circular, 13);
+
+        assert_eq!(truncations(3797, 10), vec![3797, 797, 97, 7, 379, 37, 3]);
+        assert_eq!(truncations(7, 10), vec![7]);
+        assert_eq!(truncations(3037, 10), vec![3037, 37, 37, 7, 303, 30, 3]);
+        // 左右どちらから切り詰めても素数である素数は11個
+        let truncatable = (10..1_000_000)
+            .filter(|&n| truncations(n, 10).into_iter().all(is_prime))
+            .collect::<Vec<_>>();
+        assert_eq!(truncatable.len(), 11);
+        assert_eq!(truncatable.iter().sum::<u64>(), 748_317);
+    }
+
+    #[test]
+    fn test_is_pandigital() {
+        assert!(is_pandigital(123_456_789, 1..=9));
+        assert!(is_pandigital(918_273_645, 1..=9));
+        assert!(!is_pandigital(123_456_788, 1..=9));
+        assert!(!is_pandigital(12_345_678, 1..=9));
+        assert!(!is_pandigital(1_234_567_890, 1..=9));
+        assert!(is_pandigital(1_234_567_890, 0..=9));
+        assert!(is_pandigital(2143, 1..=4));
+        assert!(!is_pandigital(2143, 1..=5));
+        assert!(is_pandigital(0, 0..=0));
+        assert_eq!((1..10_000).filter(|&n| is_pandigital(n, 1..=4)).count(), 24);
+    }
+
+    #[test]
+    fn test_concat() {
+        assert_eq!(concat(12, 345), Some(12345));
+        assert_eq!(concat(1, 0), Some(10));
+        assert_eq!(concat(0, 7), Some(7));
+        assert_eq!(concat(192, 384), Some(192_384));
+        assert_eq!(concat(1_844_674_407, 3_709_551_615), Some(u64::MAX));
+        assert_eq!(concat(1_844_674_407, 3_709_551_616), None);
+        assert_eq!(concat(10_000_000_000, 10_000_000_000), None);
+        assert_eq!(concat(0, u64::MAX), Some(u64::MAX));
+        assert_eq!(concat(1, u64::MAX), None);
+        // 連結した数も素数になる素数の組
+        assert!([3, 7, 109, 673].iter().all(|&p| {
+            [3, 7, 109, 673].iter().all(|&q| p == q || is_prime(concat(p, q).unwrap()))
+        }));
+    }
+}
@@ -0,0 +1,156 @@
+use crate::prime::factorize;
+
+
+// nの約数を昇順に返す
+pub fn divisors(n: u64) -> std::vec::IntoIter<u64> {
+    let mut result = vec![1];
+    for (p, e) in factorize(n) {
+        // これまでの約数それぞれにp^1, p^2, ..., p^eをかけたものを追加する
+        let len = result.len();
+        let mut p_pow = 1;
+        for _ in 0..e {
+            p_pow *= p;
+            for i in 0..len {
+                result.push(result[i] * p_pow);
+            }
+        }
+    }
+    result.sort_unstable();
+    result.into_iter()
+}
+
+
+// d(n) = ∏(e_i + 1)
+pub fn divisor_count(n: u64) -> u64 {
+    factorize(n).iter().map(|&(_, e)| e as u64 + 1).product()
+}
+
+
+// σ_1(n) (u64に収まらないときはpanicする)
+pub fn divisor_sum(n: u64) -> u64 {
+    sigma_k(n, 1).expect("divisor sum overflows u64")
+}
+
+
+// σ_k(n) = ∏(1 + p_i^k + p_i^(2k) + ... + p_i^(e_i * k))
+// u64に収まらないときはNone
+pub fn sigma_k(n: u64, k: u32) -> Option<u64> {
+    factorize(n).iter().try_fold(1u64, |acc, &(p, e)| {
+        let p_k = p.checked_pow(k)?;
+        let mut term = 1u64;
+        let mut p_k_pow = 1u64;
+        for _ in 0..e {
+            p_k_pow = p_k_pow.checked_mul(p_k)?;
+            term = term.checked_add(p_k_pow)?;
+        }
+        acc.checked_mul(term)
+    })
+}
+
+
+// n自身を除く約数の和
+pub fn proper_divisor_sum(n: u64) -> u64 {
+    divisor_sum(n) - n
+}
+
+
+// 0..=limitの各nについてd(n)を求める (d(0)は0とする)
+pub fn divisor_count_sieve(limit: usize) -> Vec<u64> {
+    sigma_k_sieve(limit, 0)
+}
+
+
+// 0..=limitの各nについてσ_1(n)を求める (σ_1(0)は0とする)
+pub fn divisor_sum_sieve(limit: usize) -> Vec<u64> {
+    sigma_k_sieve(limit, 1)
+}
+
+
+// 0..=limitの各nについてσ_k(n)を求める (σ_k(0)は0とする)
+// u64に収まらない値があるときはpanicする
+pub fn sigma_k_sieve(limit: usize, k: u32) -> Vec<u64> {
+    let mut result = vec![0u64; limit + 1];
+    // dの倍数すべてにd^kを足し込む
+    for d in 1..=limit {
+        let d_k = (d as u64).checked_pow(k).expect("sigma_k overflows u64");
+        for m in (d..=limit).step_by(d) {
+            result[m] = result[m].checked_add(d_k).expect("sigma_k overflows u64");
+        }
+    }
+    result
+}
+
+
+// 0..=limitの各nについてn自身を除く約数の和を求める
+pub fn proper_divisor_sum_sieve(limit: usize) -> Vec<u64> {
+    let mut result = divisor_sum_sieve(limit);
+    for (n, sum) in result.iter_mut().enumerate() {
+        *sum -= n as u64;
+    }
+    result
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn brute_force_divisors(n: u64) -> Vec<u64> {
+        (1..=n).filter(|d| n.is_multiple_of(*d)).collect()
+    }
+
+    #[test]
+    fn test_divisors() {
+        assert_eq!(divisors(1).collect::<Vec<_>>(), vec![1]);
+        assert_eq!(divisors(28).collect::<Vec<_>>(), vec![1, 2, 4, 7, 14, 28]);
+        assert_eq!(divisors(36).collect::<Vec<_>>(), vec![1, 2, 3, 4, 6, 9, 12, 18, 36]);
+        for n in 1..=1000 {
+            assert_eq!(divisors(n).collect::<Vec<_>>(), brute_force_divisors(n));
+        }
+    }
+
+    #[test]
+    fn test_divisor_functions() {
+        assert_eq!(divisor_count(28), 6);
+        assert_eq!(divisor_sum(28), 56);
+        assert_eq!(proper_divisor_sum(220), 284);
+        assert_eq!(proper_divisor_sum(284), 220);
+        assert_eq!(sigma_k(12, 2), Some(1 + 4 + 9 + 16 + 36 + 144));
+        for n in 1..=1000 {
+            let divisors = brute_force_divisors(n);
+            assert_eq!(divisor_count(n), divisors.len() as u64);
+            assert_eq!(divisor_sum(n), divisors.iter().sum::<u64>());
+            assert_eq!(sigma_k(n, 0), Some(divisors.len() as u64));
+            assert_eq!(sigma_k(n, 3), Some(divisors.iter().map(|d| d.pow(3)).sum::<u64>()));
+        }
+    }
+
+    #[test]
+    fn test_sigma_k_overflow() {
+        // σ_2(2^40) = (4^41 - 1) / 3 はu64に収まらない
+        assert_eq!(sigma_k(1 << 40, 2), None);
+        assert_eq!(sigma_k(1 << 31, 2), Some(u64::MAX / 3));
+        assert_eq!(sigma_k(1 << 63, 1), Some(u64::MAX));
+        assert_eq!(sigma_k(3 << 62, 1), None);
+        assert_eq!(sigma_k(1 << 32, 2), None);
+        assert_eq!(sigma_k(u64::MAX, 0), Some(128));
+        assert_eq!(sigma_k(10, 64), None);
+    }
+
+    #[test]
+    fn test_sieves() {
+        const LIMIT: usize = 1000;
+        let counts = divisor_count_sieve(LIMIT);
+        let sums = divisor_sum_sieve(LIMIT);
+        let proper_sums = proper_divisor_sum_sieve(LIMIT);
+        let sigma_2 = sigma_k_sieve(LIMIT, 2);
+        assert_eq!(counts[0], 0);
+        assert_eq!(sums[0], 0);
+        for n in 1..=LIMIT {
+            assert_eq!(counts[n], divisor_count(n as u64));
+            assert_eq!(sums[n], divisor_sum(n as u64));
+            assert_eq!(proper_sums[n], proper_divisor_sum(n as u64));
+            assert_eq!(Some(sigma_2[n]), sigma_k(n as u64, 2));
+        }
+    }
+}
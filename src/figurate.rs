@@ -0,0 +1,339 @@
+use crate::utils::{icbrt_u128, isqrt_u128};
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::iter::Peekable;
+
+
+// s角数の第n項
+//   P(s, n) = ((s - 2)n^2 - (s - 4)n) / 2
+// s = 3, 4, 5, 6で三角数、四角数、五角数、六角数
+// u64に収まらなければpanic
+pub fn polygonal(s: u64, n: u64) -> u64 {
+    assert!(s >= 3, "s must be at least 3");
+    checked_polygonal(s, n).expect("polygonal number overflows u64")
+}
+
+// P(s, n) = n((s - 2)(n - 1) + 2) / 2 (u64に収まらなければNone)
+fn checked_polygonal(s: u64, n: u64) -> Option<u64> {
+    if n == 0 {
+        return Some(0);
+    }
+    let (s, n) = (s as u128, n as u128);
+    let value = n.checked_mul((s - 2).checked_mul(n - 1)? + 2)? / 2;
+    u64::try_from(value).ok()
+}
+
+
+// xがs角数ならその項番号n
+// (s - 2)n^2 - (s - 4)n - 2x = 0を解くと
+//   n = ((s - 4) + √((s - 4)^2 + 8(s - 2)x)) / 2(s - 2)
+// なので根号の中が平方数で、分子が割り切れるかを調べる
+pub fn is_polygonal(s: u64, x: u64) -> Option<u64> {
+    assert!(s >= 3, "s must be at least 3");
+    // x = 0の解n = 0は、s > 4では上の式ではなくもう一方の解になる
+    if x == 0 {
+        return Some(0);
+    }
+    let offset = s as i128 - 4;             // s = 3のときだけ負
+    let offset_square = offset.unsigned_abs().pow(2);
+    // sとxがともに大きいと判別式はu128に収まらない
+    let discriminant = match (8 * (s as u128 - 2)).checked_mul(x as u128).and_then(|d| d.checked_add(offset_square)) {
+        Some(discriminant) => discriminant,
+        None => return search_polygonal(s, x),
+    };
+    let root = isqrt_u128(discriminant);
+    if root.checked_mul(root) != Some(discriminant) {
+        return None;
+    }
+    let numerator = (root as i128 + offset) as u128;
+    let denominator = 2 * (s as u128 - 2);
+    if !numerator.is_multiple_of(denominator) {
+        return None;
+    }
+    Some((numerator / denominator) as u64)
+}
+
+// 判別式がu128に収まらないときはP(s, n) = xとなるnを二分探索で探す
+// P(s, n) >= n(n + 1)/2なので、u64に収まるのはn < 2^33
+fn search_polygonal(s: u64, x: u64) -> Option<u64> {
+    let (mut low, mut high) = (1u64, 1u64 << 33);
+    while low < high {
+        let mid = low + (high - low) / 2;
+        match checked_polygonal(s, mid) {
+            Some(value) if value < x => low = mid + 1,
+            _ => high = mid,
+        }
+    }
+    if checked_polygonal(s, low) == Some(x) { Some(low) } else { None }
+}
+
+
+// 中心付きs角数の第n項 (n = 0, 1, 2, ...で1, s + 1, 3s + 1, ...)
+//   C(s, n) = sn(n + 1)/2 + 1
+// u64に収まらなければpanic
+pub fn centered_polygonal(s: u64, n: u64) -> u64 {
+    assert!(s >= 3, "s must be at least 3");
+    checked_centered_polygonal(s, n).expect("centered polygonal number overflows u64")
+}
+
+fn checked_centered_polygonal(s: u64, n: u64) -> Option<u64> {
+    let (s, n) = (s as u128, n as u128);
+    let value = s.checked_mul(n * (n + 1) / 2)? + 1;
+    u64::try_from(value).ok()
+}
+
+
+// xが中心付きs角数ならその項番号n
+// (x - 1) * 2 / s = n(n + 1)なので、三角数かどうかに帰着する
+pub fn is_centered_polygonal(s: u64, x: u64) -> Option<u64> {
+    assert!(s >= 3, "s must be at least 3");
+    if x == 0 || !(x - 1).is_multiple_of(s) {
+        return None;
+    }
+    let t = (x - 1) / s;
+    // t = n(n + 1)/2は第n項の三角数 (t = 0ならn = 0)
+    is_polygonal(3, t)
+}
+
+
+// s角錐数の第n項 (s角数の第1項から第n項までの和)
+//   n(n + 1)((s - 2)n - (s - 5)) / 6
+// s = 3で四面体数、s = 4で四角錐数
+// u64に収まらなければpanic
+pub fn pyramidal(s: u64, n: u64) -> u64 {
+    assert!(s >= 3, "s must be at least 3");
+    checked_pyramidal(s, n).expect("pyramidal number overflows u64")
+}
+
+fn checked_pyramidal(s: u64, n: u64) -> Option<u64> {
+    u64::try_from(pyramidal_u128(s, n)?).ok()
+}
+
+// n(n + 1)((s - 2)(n - 1) + 3) / 6 (u128に収まらなければNone)
+fn pyramidal_u128(s: u64, n: u64) -> Option<u128> {
+    if n == 0 {
+        return Some(0);
+    }
+    let (s, n) = (s as u128, n as u128);
+    let factor = (s - 2).checked_mul(n - 1)? + 3;
+    Some(n.checked_mul(n + 1)?.checked_mul(factor)? / 6)
+}
+
+
+// xがs角錐数ならその項番号n
+// s >= 3ならs角錐数 > n^3/6なので、n <= ∛(6x)の範囲で二分探索する
+// (6xはu64に収まらないことがあるのでu128で求める)
+pub fn is_pyramidal(s: u64, x: u64) -> Option<u64> {
+    assert!(s >= 3, "s must be at least 3");
+    let value = |n: u64| pyramidal_u128(s, n).unwrap_or(u128::MAX);
+    let (mut lo, mut hi) = (0, icbrt_u128(6 * x as u128) as u64 + 1);
+    // value(lo) <= x < value(hi)
+    while hi - lo > 1 {
+        let mid = lo + (hi - lo) / 2;
+        if value(mid) <= x as u128 {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    if value(lo) == x as u128 { Some(lo) } else { None }
+}
+
+
+// 項番号から値を求める関数fで作った数列 f(s, start), f(s, start + 1), ...
+// 値がu64に収まらなくなったところで終わる
+pub struct FigurateNumbers {
+    s: u64,
+    n: u64,
+    f: fn(u64, u64) -> Option<u64>,
+}
+
+impl Iterator for FigurateNumbers {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let result = (self.f)(self.s, self.n)?;
+        self.n += 1;
+        Some(result)
+    }
+}
+
+
+// s角数 1, s, 3s - 3, ...
+pub fn polygonal_numbers(s: u64) -> FigurateNumbers {
+    assert!(s >= 3, "s must be at least 3");
+    FigurateNumbers { s, n: 1, f: checked_polygonal }
+}
+
+// 中心付きs角数 1, s + 1, 3s + 1, ...
+pub fn centered_polygonal_numbers(s: u64) -> FigurateNumbers {
+    assert!(s >= 3, "s must be at least 3");
+    FigurateNumbers { s, n: 0, f: checked_centered_polygonal }
+}
+
+// s角錐数 1, s + 1, 4s - 2, ...
+pub fn pyramidal_numbers(s: u64) -> FigurateNumbers {
+    assert!(s >= 3, "s must be at least 3");
+    FigurateNumbers { s, n: 1, f: checked_pyramidal }
+}
+
+
+// 昇順に並んだ二つの数列の共通部分を昇順に返すイテレータ
+pub struct Intersection<I: Iterator, J: Iterator> {
+    a: Peekable<I>,
+    b: Peekable<J>,
+}
+
+impl<I, J> Iterator for Intersection<I, J>
+where
+    I: Iterator<Item = u64>,
+    J: Iterator<Item = u64>,
+{
+    type Item = u64;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (&x, &y) = (self.a.peek()?, self.b.peek()?);
+            match x.cmp(&y) {
+                Ordering::Less => { self.a.next(); }
+                Ordering::Greater => { self.b.next(); }
+                Ordering::Equal => {
+                    self.a.next();
+                    self.b.next();
+                    return Some(x);
+                }
+            }
+        }
+    }
+}
+
+pub fn intersection<I, J>(a: I, b: J) -> Intersection<I::IntoIter, J::IntoIter>
+where
+    I: IntoIterator<Item = u64>,
+    J: IntoIterator<Item = u64>,
+{
+    Intersection { a: a.into_iter().peekable(), b: b.into_iter().peekable() }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_polygonal() {
+        let test_cases: &[(u64, &[u64])] = &[
+            (3, &[1, 3, 6, 10, 15, 21]),
+            (4, &[1, 4, 9, 16, 25, 36]),
+            (5, &[1, 5, 12, 22, 35, 51]),
+            (6, &[1, 6, 15, 28, 45, 66]),
+            (7, &[1, 7, 18, 34, 55, 81]),
+            (8, &[1, 8, 21, 40, 65, 96]),
+        ];
+        for &(s, expected) in test_cases {
+            assert_eq!(polygonal_numbers(s).take(6).collect::<Vec<_>>(), expected);
+            assert_eq!(polygonal(s, 0), 0);
+        }
+        for s in 3..=12 {
+            let numbers = polygonal_numbers(s).take(200).collect::<Vec<_>>();
+            for x in 0..=*numbers.last().unwrap() {
+                let expected = if x == 0 { Some(0) } else { numbers.iter().position(|&p| p == x).map(|i| i as u64 + 1) };
+                assert_eq!(is_polygonal(s, x), expected, "{} {}", s, x);
+            }
+        }
+        // 大きな値でも正確に判定できる
+        let n = 3_000_000_000;
+        assert_eq!(is_polygonal(3, polygonal(3, n)), Some(n));
+        assert_eq!(is_polygonal(3, polygonal(3, n) + 1), None);
+        assert_eq!(is_polygonal(5, polygonal(5, n)), Some(n));
+        assert_eq!(is_polygonal(5, polygonal(5, n) - 1), None);
+        assert_eq!(is_polygonal(1000, polygonal(1000, 100_000)), Some(100_000));
+        // 判別式がu128に収まらない場合 (P(s, 1) = 1, P(s, 2) = s)
+        assert_eq!(is_polygonal(u64::MAX, u64::MAX), Some(2));
+        assert_eq!(is_polygonal(1 << 63, 1 << 63), Some(2));
+        assert_eq!(is_polygonal(u64::MAX, 1), Some(1));
+        assert_eq!(is_polygonal(u64::MAX, u64::MAX - 1), None);
+        assert_eq!(is_polygonal(1 << 62, polygonal(1 << 62, 3)), Some(3));
+        assert_eq!(is_polygonal(1 << 62, polygonal(1 << 62, 3) - 1), None);
+        assert_eq!(is_polygonal(1 << 62, polygonal(1 << 62, 3) + 1), None);
+        assert_eq!(is_polygonal(3, u64::MAX), None);
+        assert_eq!(is_polygonal(3, polygonal(3, 6_074_000_999)), Some(6_074_000_999));
+        assert_eq!(is_polygonal(4, 4_294_967_295 * 4_294_967_295), Some(4_294_967_295));
+    }
+
+    #[test]
+    fn test_overflow() {
+        // 三角数T(n)がu64に収まる最大のnは6074000999
+        assert_eq!(polygonal(3, 6_074_000_999), 18_446_744_070_963_499_500);
+        assert_eq!(checked_polygonal(3, 6_074_001_000), None);
+        assert_eq!(checked_polygonal(3, 1 << 33), None);
+        assert_eq!(checked_polygonal(u64::MAX, u64::MAX), None);
+        assert_eq!(checked_centered_polygonal(3, 1 << 32), None);
+        assert_eq!(checked_pyramidal(3, 1 << 22), Some(12_297_838_178_567_454_720));
+        assert_eq!(checked_pyramidal(3, 1 << 23), None);
+        assert_eq!(checked_pyramidal(u64::MAX, u64::MAX), None);
+        assert!(std::panic::catch_unwind(|| polygonal(3, 1 << 33)).is_err());
+
+        // 数列はu64に収まらなくなったところで終わる
+        let s = 1 << 40;
+        let numbers = polygonal_numbers(s).collect::<Vec<_>>();
+        assert_eq!(numbers.len(), 5793);
+        assert_eq!(*numbers.last().unwrap(), polygonal(s, 5793));
+        assert_eq!(centered_polygonal_numbers(s).count(), 5793);
+        assert_eq!(pyramidal_numbers(s).last(), Some(pyramidal(s, 465)));
+    }
+
+    #[test]
+    fn test_centered_polygonal() {
+        assert_eq!(centered_polygonal_numbers(3).take(6).collect::<Vec<_>>(), vec![1, 4, 10, 19, 31, 46]);
+        assert_eq!(centered_polygonal_numbers(4).take(6).collect::<Vec<_>>(), vec![1, 5, 13, 25, 41, 61]);
+        assert_eq!(centered_polygonal_numbers(6).take(6).collect::<Vec<_>>(), vec![1, 7, 19, 37, 61, 91]);
+        for s in 3..=10 {
+            let numbers = centered_polygonal_numbers(s).take(100).collect::<Vec<_>>();
+            for x in 0..=*numbers.last().unwrap() {
+                let expected = numbers.iter().position(|&c| c == x).map(|i| i as u64);
+                assert_eq!(is_centered_polygonal(s, x), expected, "{} {}", s, x);
+            }
+        }
+    }
+
+    #[test]
+    fn test_pyramidal() {
+        assert_eq!(pyramidal_numbers(3).take(6).collect::<Vec<_>>(), vec![1, 4, 10, 20, 35, 56]);
+        assert_eq!(pyramidal_numbers(4).take(6).collect::<Vec<_>>(), vec![1, 5, 14, 30, 55, 91]);
+        assert_eq!(pyramidal_numbers(5).take(6).collect::<Vec<_>>(), vec![1, 6, 18, 40, 75, 126]);
+        for s in 3..=8 {
+            // s角錐数はs角数の部分和
+            let mut sum = 0;
+            for (n, p) in polygonal_numbers(s).take(50).enumerate() {
+                sum += p;
+                assert_eq!(pyramidal(s, n as u64 + 1), sum);
+            }
+            let numbers = pyramidal_numbers(s).take(60).collect::<Vec<_>>();
+            for x in 0..=*numbers.last().unwrap() {
+                let expected = if x == 0 { Some(0) } else { numbers.iter().position(|&p| p == x).map(|i| i as u64 + 1) };
+                assert_eq!(is_pyramidal(s, x), expected, "{} {}", s, x);
+            }
+        }
+        assert_eq!(is_pyramidal(4, pyramidal(4, 1_000_000)), Some(1_000_000));
+        // 6xがu64に収まらない大きな値
+        for &n in &[2_700_000, 3_000_000, 4_000_000, 4_801_000] {
+            assert_eq!(is_pyramidal(3, pyramidal(3, n)), Some(n));
+            assert_eq!(is_pyramidal(3, pyramidal(3, n) - 1), None);
+        }
+        assert_eq!(is_pyramidal(5, pyramidal(5, 3_000_000)), Some(3_000_000));
+        assert_eq!(is_pyramidal(4, u64::MAX), None);
+    }
+
+    #[test]
+    fn test_intersection() {
+        // 三角数かつ五角数かつ六角数
+        let numbers = intersection(intersection(polygonal_numbers(3), polygonal_numbers(5)), polygonal_numbers(6));
+        assert_eq!(numbers.take(3).collect::<Vec<_>>(), vec![1, 40755, 1_533_776_805]);
+        // 三角数かつ四角数
+        let numbers = intersection(polygonal_numbers(3), polygonal_numbers(4)).take(5).collect::<Vec<_>>();
+        assert_eq!(numbers, vec![1, 36, 1225, 41616, 1_413_721]);
+        assert_eq!(intersection(vec![1, 3, 5, 7], vec![2, 3, 4, 7, 8]).collect::<Vec<_>>(), vec![3, 7]);
+        assert_eq!(intersection(vec![], 1..).next(), None);
+    }
+}
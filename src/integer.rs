@@ -0,0 +1,203 @@
+use crate::bigint::BigUInt;
+use crate::utils::mulmod_u128;
+use std::fmt::Debug;
+
+
+// 符号なし整数型に共通する操作
+// プリミティブ型もBigUIntも同じ関数で扱えるよう、引数はすべて参照で受け取る
+pub trait Integer: Clone + Ord + Debug {
+    fn zero() -> Self;
+    fn one() -> Self;
+    fn from_u32(n: u32) -> Self;
+    fn checked_add(&self, rhs: &Self) -> Option<Self>;
+    fn checked_sub(&self, rhs: &Self) -> Option<Self>;
+    fn checked_mul(&self, rhs: &Self) -> Option<Self>;
+    // 商と余り (rhsが0ならpanic)
+    fn div_rem(&self, rhs: &Self) -> (Self, Self);
+    fn is_even(&self) -> bool;
+    // self * rhs mod m (途中でオーバーフローしない)
+    fn mul_mod(&self, rhs: &Self, m: &Self) -> Self;
+
+    fn is_zero(&self) -> bool {
+        *self == Self::zero()
+    }
+
+    fn is_odd(&self) -> bool {
+        !self.is_even()
+    }
+}
+
+
+// u128より小さい型はu128に広げて掛け算する
+macro_rules! impl_integer {
+    ($($t:ty),*) => {$(
+        impl Integer for $t {
+            fn zero() -> Self { 0 }
+            fn one() -> Self { 1 }
+            fn from_u32(n: u32) -> Self { n as Self }
+            fn checked_add(&self, rhs: &Self) -> Option<Self> { <$t>::checked_add(*self, *rhs) }
+            fn checked_sub(&self, rhs: &Self) -> Option<Self> { <$t>::checked_sub(*self, *rhs) }
+            fn checked_mul(&self, rhs: &Self) -> Option<Self> { <$t>::checked_mul(*self, *rhs) }
+            fn div_rem(&self, rhs: &Self) -> (Self, Self) { (*self / *rhs, *self % *rhs) }
+            fn is_even(&self) -> bool { *self & 1 == 0 }
+            fn mul_mod(&self, rhs: &Self, m: &Self) -> Self {
+                (*self as u128 * *rhs as u128 % *m as u128) as Self
+            }
+        }
+    )*};
+}
+
+impl_integer!(u8, u16, u32, u64, usize);
+
+impl Integer for u128 {
+    fn zero() -> Self { 0 }
+    fn one() -> Self { 1 }
+    fn from_u32(n: u32) -> Self { n as Self }
+    fn checked_add(&self, rhs: &Self) -> Option<Self> { u128::checked_add(*self, *rhs) }
+    fn checked_sub(&self, rhs: &Self) -> Option<Self> { u128::checked_sub(*self, *rhs) }
+    fn checked_mul(&self, rhs: &Self) -> Option<Self> { u128::checked_mul(*self, *rhs) }
+    fn div_rem(&self, rhs: &Self) -> (Self, Self) { (*self / *rhs, *self % *rhs) }
+    fn is_even(&self) -> bool { *self & 1 == 0 }
+    fn mul_mod(&self, rhs: &Self, m: &Self) -> Self {
+        mulmod_u128(*self % *m, *rhs % *m, *m)
+    }
+}
+
+impl Integer for BigUInt {
+    fn zero() -> Self { BigUInt::new(0) }
+    fn one() -> Self { BigUInt::new(1) }
+    fn from_u32(n: u32) -> Self { BigUInt::new(n) }
+    fn checked_add(&self, rhs: &Self) -> Option<Self> { Some(self.clone() + rhs.clone()) }
+    fn checked_sub(&self, rhs: &Self) -> Option<Self> { BigUInt::checked_sub(self, rhs) }
+    fn checked_mul(&self, rhs: &Self) -> Option<Self> { Some(self.clone() * rhs.clone()) }
+    fn div_rem(&self, rhs: &Self) -> (Self, Self) { BigUInt::div_rem(self, rhs) }
+    fn is_even(&self) -> bool { BigUInt::is_even(self) }
+    fn is_zero(&self) -> bool { BigUInt::is_zero(self) }
+    fn mul_mod(&self, rhs: &Self, m: &Self) -> Self {
+        (self.clone() * rhs.clone()).div_rem(m).1
+    }
+}
+
+
+// 最大公約数 (ユークリッドの互除法)
+pub fn gcd<T: Integer>(mut a: T, mut b: T) -> T {
+    while !b.is_zero() {
+        a = a.div_rem(&b).1;
+        std::mem::swap(&mut a, &mut b);
+    }
+    a
+}
+
+
+// a^n mod m (繰り返し二乗法)
+pub fn modpow<T: Integer>(a: T, n: T, m: T) -> T {
+    let two = T::from_u32(2);
+    let mut result = T::one().div_rem(&m).1;
+    let mut base = a.div_rem(&m).1;
+    let mut n = n;
+    while !n.is_zero() {
+        if n.is_odd() {
+            result = result.mul_mod(&base, &m);
+        }
+        base = base.mul_mod(&base, &m);
+        n = n.div_rem(&two).0;
+    }
+    result
+}
+
+
+// 10進数で回文になっているか
+pub fn is_palindrome<T: Integer>(n: T) -> bool {
+    let ten = T::from_u32(10);
+    let mut digits = Vec::new();
+    let mut n = n;
+    loop {
+        let (q, r) = n.div_rem(&ten);
+        digits.push(r);
+        if q.is_zero() {
+            break;
+        }
+        n = q;
+    }
+    digits.iter().eq(digits.iter().rev())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn big(n: u64) -> BigUInt {
+        let (hi, lo) = ((n >> 32) as u32, n as u32);
+        BigUInt::new(hi) * BigUInt::new(1 << 16) * BigUInt::new(1 << 16) + BigUInt::new(lo)
+    }
+
+    #[test]
+    fn test_integer() {
+        assert_eq!(Integer::checked_add(&250u8, &5), Some(255));
+        assert_eq!(Integer::checked_add(&250u8, &6), None);
+        assert_eq!(Integer::checked_sub(&3u32, &4), None);
+        assert_eq!(Integer::checked_mul(&(1u128 << 64), &(1 << 63)), Some(1 << 127));
+        assert_eq!(Integer::checked_mul(&(1u128 << 64), &(1 << 64)), None);
+        assert_eq!(Integer::div_rem(&17u64, &5), (3, 2));
+        assert!(Integer::is_even(&0u16));
+        assert!(Integer::is_odd(&u128::MAX));
+        assert_eq!(Integer::mul_mod(&u64::MAX, &u64::MAX, &(u64::MAX - 1)), 1);
+        assert_eq!(Integer::mul_mod(&u128::MAX, &u128::MAX, &(u128::MAX - 1)), 1);
+
+        assert_eq!(big(u64::MAX).checked_sub(&big(1)), Some(big(u64::MAX - 1)));
+        assert_eq!(Integer::div_rem(&big(u64::MAX), &big(1 << 32)), (big(u32::MAX as u64), big(u32::MAX as u64)));
+        assert!(big(1 << 40).is_even() && big(12_345).is_odd());
+        assert!(BigUInt::zero().is_zero() && !BigUInt::one().is_zero());
+    }
+
+    #[test]
+    fn test_gcd() {
+        assert_eq!(gcd(24u32, 16), 8);
+        assert_eq!(gcd(0u64, 7), 7);
+        assert_eq!(gcd(7u8, 0), 7);
+        assert_eq!(gcd(1u128 << 100, 6u128.pow(40)), 1 << 40);
+        assert_eq!(gcd(big(600_851_475_143), big(6857 * 13)), big(6857));
+        for a in 0..50u64 {
+            for b in 0..50u64 {
+                assert_eq!(gcd(big(a), big(b)), big(gcd(a, b)));
+                assert_eq!(gcd(a as u128, b as u128), gcd(a, b) as u128);
+            }
+        }
+    }
+
+    #[test]
+    fn test_modpow() {
+        for a in 0..20u64 {
+            for n in 1..20u64 {
+                for m in 1..30u64 {
+                    let expected = crate::utils::modpow(a, n, m);
+                    assert_eq!(modpow(a, n, m), expected);
+                    assert_eq!(modpow(a as u32, n as u32, m as u32), expected as u32);
+                    assert_eq!(modpow(big(a), big(n), big(m)), big(expected));
+                }
+            }
+        }
+        assert_eq!(modpow(5u64, 0, 1), 0);
+        assert_eq!(modpow(5u64, 0, 7), 1);
+        let m127 = (1u128 << 127) - 1;
+        assert_eq!(modpow(3, m127 - 1, m127), 1);
+        let p = 18_446_744_073_709_551_557;
+        assert_eq!(modpow(big(2), big(p - 1), big(p)), big(1));
+    }
+
+    #[test]
+    fn test_is_palindrome() {
+        for n in 0..2000u64 {
+            let s = n.to_string();
+            let expected = s.chars().eq(s.chars().rev());
+            assert_eq!(is_palindrome(n), expected);
+            assert_eq!(is_palindrome(n as u16), expected);
+            assert_eq!(is_palindrome(big(n)), expected);
+        }
+        assert!(is_palindrome(12_345_678_987_654_321u64));
+        assert!(!is_palindrome(u128::MAX));
+        assert!(is_palindrome(big(11) * big(11)));
+        assert!(is_palindrome(big(1_000_000_001) * big(1_000_000_001)));
+    }
+}
@@ -1,4 +1,23 @@
 pub mod utils;
+pub mod integer;
+pub mod digits;
+pub mod figurate;
+pub mod permutations;
+pub mod binomial;
+pub mod matrix;
 pub mod continued_fraction;
 pub mod bigint;
 pub mod prime;
+pub mod divisors;
+pub mod totient;
+pub mod mobius;
+pub mod multiplicative;
+pub mod montgomery;
+pub mod modint;
+pub mod parallel_sieve;
+pub mod prime_cache;
+pub mod certificate;
+pub mod quadratic_residue;
+pub mod multiplicative_group;
+pub mod prime_tuples;
+pub mod partitions;
@@ -0,0 +1,276 @@
+use crate::utils::{addmod, mulmod};
+use std::ops::{Add, Index, IndexMut, Mul};
+
+
+// 行列
+// 要素は足し算と掛け算ができて、From<u32>で0と1が作れるもの (整数、BigUIntなど)
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Matrix<T> {
+    rows: usize,
+    cols: usize,
+    data: Vec<T>,
+}
+
+impl<T> Matrix<T>
+where
+    T: Clone + Add<Output = T> + Mul<Output = T> + From<u32>,
+{
+    pub fn zeros(rows: usize, cols: usize) -> Self {
+        Self { rows, cols, data: vec![T::from(0); rows * cols] }
+    }
+
+    pub fn identity(n: usize) -> Self {
+        let mut result = Self::zeros(n, n);
+        for i in 0..n {
+            result[(i, i)] = T::from(1);
+        }
+        result
+    }
+
+    // 各行を並べたものから作る (行の長さはすべて等しいこと)
+    pub fn from_rows(rows: Vec<Vec<T>>) -> Self {
+        let cols = rows.first().map_or(0, |row| row.len());
+        assert!(rows.iter().all(|row| row.len() == cols), "rows must have the same length");
+        Self { rows: rows.len(), cols, data: rows.into_iter().flatten().collect() }
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    // self^n (繰り返し二乗法、正方行列のみ)
+    pub fn pow(&self, mut n: u64) -> Self {
+        assert_eq!(self.rows, self.cols, "matrix must be square");
+        let mut result = Self::identity(self.rows);
+        let mut base = self.clone();
+        while n != 0 {
+            if n & 1 == 1 {
+                result = &result * &base;
+            }
+            n >>= 1;
+            if n != 0 {
+                base = &base * &base;
+            }
+        }
+        result
+    }
+}
+
+impl<T> Index<(usize, usize)> for Matrix<T> {
+    type Output = T;
+
+    fn index(&self, (i, j): (usize, usize)) -> &T {
+        assert!(i < self.rows && j < self.cols, "index out of bounds");
+        &self.data[i * self.cols + j]
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for Matrix<T> {
+    fn index_mut(&mut self, (i, j): (usize, usize)) -> &mut T {
+        assert!(i < self.rows && j < self.cols, "index out of bounds");
+        &mut self.data[i * self.cols + j]
+    }
+}
+
+impl<T> Mul for &Matrix<T>
+where
+    T: Clone + Add<Output = T> + Mul<Output = T> + From<u32>,
+{
+    type Output = Matrix<T>;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        assert_eq!(self.cols, rhs.rows, "dimension mismatch");
+        let mut result = Matrix::<T>::zeros(self.rows, rhs.cols);
+        for i in 0..self.rows {
+            for k in 0..self.cols {
+                let a = &self[(i, k)];
+                for j in 0..rhs.cols {
+                    let sum = result[(i, j)].clone() + a.clone() * rhs[(k, j)].clone();
+                    result[(i, j)] = sum;
+                }
+            }
+        }
+        result
+    }
+}
+
+impl<T> Mul for Matrix<T>
+where
+    T: Clone + Add<Output = T> + Mul<Output = T> + From<u32>,
+{
+    type Output = Matrix<T>;
+
+    fn mul(self, rhs: Self) -> Self::Output {
+        &self * &rhs
+    }
+}
+
+
+// 線形漸化式
+//   a_n = c_1 a_(n-1) + c_2 a_(n-2) + ... + c_d a_(n-d)
+// の第n項 mod modulus (coeffs = [c_1, ..., c_d], initial = [a_0, ..., a_(d-1)])
+//
+// きたまさ法: 特性多項式f(x) = x^d - c_1 x^(d-1) - ... - c_dについて
+//   x^n ≡ r_0 + r_1 x + ... + r_(d-1) x^(d-1) (mod f(x))
+// ならばa_n = ∑r_i a_iとなる。x^n mod f(x)を繰り返し二乗法で求めるのでO(d^2 log n)
+pub fn linear_recurrence_nth(coeffs: &[u64], initial: &[u64], n: u64, modulus: u64) -> u64 {
+    let d = coeffs.len();
+    assert_eq!(initial.len(), d, "initial terms must match the order of the recurrence");
+    if d == 0 || modulus == 1 {
+        return 0;
+    }
+    if n < d as u64 {
+        return initial[n as usize] % modulus;
+    }
+    let coeffs = coeffs.iter().map(|&c| c % modulus).collect::<Vec<_>>();
+
+    // a(x) * b(x) mod f(x)
+    let mul = |a: &[u64], b: &[u64]| {
+        let mut product = vec![0u64; 2 * d - 1];
+        for (i, &a_i) in a.iter().enumerate() {
+            for (j, &b_j) in b.iter().enumerate() {
+                product[i + j] = addmod(product[i + j], mulmod(a_i, b_j, modulus), modulus);
+            }
+        }
+        // x^i = x^(i-d) * x^d ≡ x^(i-d) * ∑c_j x^(d-j)で次数を下げる
+        for i in (d..2 * d - 1).rev() {
+            let top = product[i];
+            for (j, &c) in coeffs.iter().enumerate() {
+                let k = i - 1 - j;
+                product[k] = addmod(product[k], mulmod(top, c, modulus), modulus);
+            }
+        }
+        product.truncate(d);
+        product
+    };
+
+    let mut result = vec![0; d];
+    result[0] = 1;
+    // x mod f(x) (d = 1ならx ≡ c_1)
+    let mut base = vec![0; d];
+    if d == 1 {
+        base[0] = coeffs[0];
+    } else {
+        base[1] = 1;
+    }
+    let mut n = n;
+    while n != 0 {
+        if n & 1 == 1 {
+            result = mul(&result, &base);
+        }
+        n >>= 1;
+        if n != 0 {
+            base = mul(&base, &base);
+        }
+    }
+    result.iter()
+        .zip(initial)
+        .fold(0, |acc, (&r, &a)| addmod(acc, mulmod(r, a % modulus, modulus), modulus))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bigint::BigUInt;
+
+    #[test]
+    fn test_matrix() {
+        let a = Matrix::from_rows(vec![vec![1u64, 2, 3], vec![4, 5, 6]]);
+        let b = Matrix::from_rows(vec![vec![7u64, 8], vec![9, 10], vec![11, 12]]);
+        assert_eq!(&a * &b, Matrix::from_rows(vec![vec![58, 64], vec![139, 154]]));
+        assert_eq!((a.rows(), a.cols()), (2, 3));
+        assert_eq!(a[(1, 2)], 6);
+        assert_eq!(&Matrix::identity(2) * &a, a);
+        assert_eq!(a.clone() * Matrix::identity(3), a);
+
+        let m = Matrix::from_rows(vec![vec![2i64, -1], vec![1, 0]]);
+        assert_eq!(m.pow(0), Matrix::identity(2));
+        assert_eq!(m.pow(1), m);
+        assert_eq!(m.pow(10), Matrix::from_rows(vec![vec![11, -10], vec![10, -9]]));
+    }
+
+    #[test]
+    fn test_fibonacci_matrix() {
+        // [[1, 1], [1, 0]]^n = [[F(n+1), F(n)], [F(n), F(n-1)]]
+        let q = Matrix::from_rows(vec![vec![1u64, 1], vec![1, 0]]);
+        let mut fib = vec![0u64, 1];
+        for i in 2..=93 {
+            fib.push(fib[i - 1] + fib[i - 2]);
+        }
+        for n in 1..93 {
+            let q_n = q.pow(n as u64);
+            assert_eq!((q_n[(0, 0)], q_n[(0, 1)], q_n[(1, 1)]), (fib[n + 1], fib[n], fib[n - 1]));
+        }
+
+        // BigUIntでは桁あふれしない
+        let q = Matrix::from_rows(vec![vec![BigUInt::from(1u32), BigUInt::from(1u32)], vec![BigUInt::from(1u32), BigUInt::from(0u32)]]);
+        let (mut a, mut b) = (BigUInt::from(0u32), BigUInt::from(1u32));
+        for _ in 0..300 {
+            let next = a.clone() + b.clone();
+            a = b;
+            b = next;
+        }
+        assert_eq!(q.pow(300)[(0, 1)], a);
+    }
+
+    #[test]
+    fn test_linear_recurrence_nth() {
+        // フィボナッチ数
+        let mut fib = vec![0u64, 1];
+        for i in 2..=93 {
+            fib.push(fib[i - 1] + fib[i - 2]);
+        }
+        for (n, &f) in fib.iter().enumerate() {
+            assert_eq!(linear_recurrence_nth(&[1, 1], &[0, 1], n as u64, u64::MAX), f);
+        }
+        assert_eq!(linear_recurrence_nth(&[1, 1], &[0, 1], 10u64.pow(18), 1_000_000_007), 209_783_453);
+        // トリボナッチ数 a_100 = 53324762928098149064722658
+        assert_eq!(linear_recurrence_nth(&[1, 1, 1], &[0, 0, 1], 100, u64::MAX), 3_517_718_926_119_625_943);
+        // 2^n
+        assert_eq!(linear_recurrence_nth(&[2], &[1], 63, u64::MAX), 1 << 63);
+        assert_eq!(linear_recurrence_nth(&[2], &[1], 1_000_000_006, 1_000_000_007), 1);
+        assert_eq!(linear_recurrence_nth(&[], &[], 5, 7), 0);
+        assert_eq!(linear_recurrence_nth(&[1, 1], &[0, 1], 10, 1), 0);
+
+        // 行列累乗や素朴な計算と一致する
+        let recurrences: &[(&[u64], &[u64])] = &[
+            (&[1, 1, 1], &[0, 0, 1]),
+            (&[3, 0, 2, 5], &[1, 4, 1, 5]),
+            (&[0, 0, 0, 0, 1], &[1, 2, 3, 4, 5]),
+            (&[7, 1_000_000, 3, 9, 2, 4], &[9, 8, 7, 6, 5, 4]),
+        ];
+        let modulus = 998_244_353;
+        for &(coeffs, initial) in recurrences {
+            let d = coeffs.len();
+            let mut terms = initial.to_vec();
+            for i in d..300 {
+                let next = (1..=d).fold(0, |acc, j| (acc + coeffs[j - 1] * terms[i - j]) % modulus);
+                terms.push(next);
+            }
+            // コンパニオン行列
+            let mut companion = Matrix::zeros(d, d);
+            for j in 0..d {
+                companion[(0, j)] = coeffs[j];
+            }
+            for i in 1..d {
+                companion[(i, i - 1)] = 1;
+            }
+            for (n, &expected) in terms.iter().enumerate() {
+                assert_eq!(linear_recurrence_nth(coeffs, initial, n as u64, modulus), expected);
+            }
+            let n = 250;
+            let power = (0..n - d + 1).fold(Matrix::identity(d), |acc: Matrix<u64>, _| {
+                let mut product = &acc * &companion;
+                product.data.iter_mut().for_each(|x| *x %= modulus);
+                product
+            });
+            let from_matrix = (0..d).fold(0, |acc, j| (acc + power[(0, j)] * initial[d - 1 - j]) % modulus);
+            assert_eq!(from_matrix, terms[n]);
+        }
+    }
+}
@@ -0,0 +1,172 @@
+use crate::prime::factorize;
+use crate::utils::{icbrt, isqrt};
+
+
+// μ(n) = 0         (nが平方因子を持つ)
+//        (-1)^k    (nが相異なるk個の素数の積)
+pub fn mobius(n: u64) -> i8 {
+    let factors = factorize(n);
+    if factors.iter().any(|&(_, e)| e > 1) {
+        return 0;
+    }
+    if factors.len().is_multiple_of(2) { 1 } else { -1 }
+}
+
+
+// 0..=limitの各nについてμ(n)を求める (μ(0)は0とする)
+pub fn mobius_sieve(limit: usize) -> Vec<i8> {
+    let mut mu = vec![1i8; limit + 1];
+    let mut is_composite = vec![false; limit + 1];
+    if let Some(mu_0) = mu.first_mut() {
+        *mu_0 = 0;
+    }
+    for p in 2..=limit {
+        if is_composite[p] {
+            continue;
+        }
+        for m in (p..=limit).step_by(p) {
+            is_composite[m] = m != p;
+            mu[m] = -mu[m];
+        }
+        // p^2の倍数は平方因子を持つ
+        if let Some(p_square) = p.checked_mul(p) {
+            for m in (p_square..=limit).step_by(p_square) {
+                mu[m] = 0;
+            }
+        }
+    }
+    mu
+}
+
+
+// M(n) = ∑_{k=1}^{n} μ(k)
+//
+// ∑_{d|k} μ(d) = [k == 1]を1..=nのkについて足し合わせると
+//   ∑_{d=1}^{n} M(⌊n/d⌋) = 1
+// なので
+//   M(n) = 1 - ∑_{d=2}^{n} M(⌊n/d⌋)
+// totient::totient_sumと同様にn^(2/3)以下は篩で、それより大きい値はメモ化再帰で求める。
+pub fn mertens(n: u64) -> i64 {
+    let limit = icbrt(n).pow(2).clamp(1, n.max(1));
+    let mut small = vec![0i64; limit as usize + 1];
+    for (i, &mu) in mobius_sieve(limit as usize).iter().enumerate().skip(1) {
+        small[i] = small[i - 1] + mu as i64;
+    }
+    // ⌊n/v⌋ (v > limit) で添字付けする
+    let mut large = vec![None; (n / limit) as usize + 1];
+    mertens_rec(n, n, &small, &mut large)
+}
+
+fn mertens_rec(v: u64, n: u64, small: &[i64], large: &mut [Option<i64>]) -> i64 {
+    if (v as usize) < small.len() {
+        return small[v as usize];
+    }
+    let index = (n / v) as usize;
+    if let Some(result) = large[index] {
+        return result;
+    }
+    let mut result = 1;
+    let mut d = 2;
+    while d <= v {
+        let q = v / d;
+        let d_max = v / q;          // ⌊v/d⌋ == qとなる最大のd
+        result -= (d_max - d + 1) as i64 * mertens_rec(q, n, small, large);
+        d = d_max + 1;
+    }
+    large[index] = Some(result);
+    result
+}
+
+
+// n以下の平方因子を持たない正の整数の個数
+//   Q(n) = ∑_{d=1}^{√n} μ(d)⌊n/d^2⌋
+pub fn count_squarefree(n: u64) -> u64 {
+    let sqrt_n = isqrt(n);
+    let mu = mobius_sieve(sqrt_n as usize);
+    // n >= 2^63ではd = 1の項だけでi64に収まらないのでi128で足し合わせる
+    let count = (1..=sqrt_n)
+        .map(|d| mu[d as usize] as i128 * (n / (d * d)) as i128)
+        .sum::<i128>();
+    count as u64
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_squarefree(n: u64) -> bool {
+        (2..).take_while(|d| d * d <= n).all(|d| !n.is_multiple_of(d * d))
+    }
+
+    // 試し割りで数えた相異なる素因数の個数
+    fn count_prime_factors(mut n: u64) -> u32 {
+        let mut count = 0;
+        let mut d = 2;
+        while d * d <= n {
+            if n.is_multiple_of(d) {
+                count += 1;
+                while n.is_multiple_of(d) {
+                    n /= d;
+                }
+            }
+            d += 1;
+        }
+        if n > 1 { count + 1 } else { count }
+    }
+
+    #[test]
+    fn test_mobius() {
+        let test_cases = &[(1, 1), (2, -1), (4, 0), (6, 1), (30, -1), (12, 0), (210, 1)];
+        for &(n, expected) in test_cases {
+            assert_eq!(mobius(n), expected);
+        }
+        // 平方因子を持てば0、そうでなければ素因数の個数kについて(-1)^k
+        for n in 1..=10000 {
+            let expected = if !is_squarefree(n) {
+                0
+            } else if count_prime_factors(n).is_multiple_of(2) {
+                1
+            } else {
+                -1
+            };
+            assert_eq!(mobius(n), expected, "{}", n);
+        }
+    }
+
+    #[test]
+    fn test_mobius_sieve() {
+        let mu = mobius_sieve(10000);
+        assert_eq!(mu[0], 0);
+        for (n, &mu_n) in mu.iter().enumerate().skip(1) {
+            assert_eq!(mu_n, mobius(n as u64));
+        }
+        assert_eq!(mobius_sieve(0), vec![0]);
+    }
+
+    #[test]
+    fn test_mertens() {
+        let mu = mobius_sieve(3000);
+        let mut sum = 0;
+        for (n, &mu_n) in mu.iter().enumerate().skip(1) {
+            sum += mu_n as i64;
+            assert_eq!(mertens(n as u64), sum);
+        }
+        assert_eq!(mertens(0), 0);
+        assert_eq!(mertens(1_000_000), 212);
+        assert_eq!(mertens(10_000_000), 1037);
+    }
+
+    #[test]
+    fn test_count_squarefree() {
+        let mut count = 0;
+        for n in 1..=3000 {
+            if is_squarefree(n) {
+                count += 1;
+            }
+            assert_eq!(count_squarefree(n), count);
+        }
+        assert_eq!(count_squarefree(0), 0);
+        assert_eq!(count_squarefree(100_000_000), 60_792_694);
+    }
+}
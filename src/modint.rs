@@ -0,0 +1,389 @@
+use crate::montgomery::Barrett64;
+use crate::utils::{addmod, mod_inverse, submod};
+use std::cell::Cell;
+use std::fmt;
+use std::iter::{Product, Sum};
+use std::marker::PhantomData;
+use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
+
+
+// ModIntBaseの法
+pub trait Modulus: Copy + Eq {
+    fn barrett() -> Barrett64;
+}
+
+// コンパイル時に決まる法M
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct StaticModulus<const M: u64>;
+
+impl<const M: u64> StaticModulus<M> {
+    const BARRETT: Barrett64 = Barrett64::new(M);
+}
+
+impl<const M: u64> Modulus for StaticModulus<M> {
+    fn barrett() -> Barrett64 {
+        Self::BARRETT
+    }
+}
+
+// 実行時に決める法 (DynamicModInt::scoped_modulusが返すガードが生きている間、そのスレッドでだけ有効)
+// 法が設定されていないスレッドで使うとpanicする。
+// 値を別のスレッドに持ち出すと法が変わってしまうので、DynamicModIntはSendにしない。
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub struct DynamicModulus(PhantomData<*const ()>);
+
+thread_local! {
+    static DYNAMIC_MODULUS: Cell<Option<Barrett64>> = const { Cell::new(None) };
+}
+
+impl Modulus for DynamicModulus {
+    fn barrett() -> Barrett64 {
+        DYNAMIC_MODULUS.with(|barrett| barrett.get())
+            .expect("DynamicModInt::scoped_modulus must be called on this thread first")
+    }
+}
+
+// DynamicModInt::scoped_modulusの戻り値。捨てるとそれまでの法に戻す
+#[must_use = "the modulus is reset when the guard is dropped"]
+pub struct ModulusGuard {
+    previous: Option<Barrett64>,
+    // 作ったスレッドで戻さないといけないのでSendにしない
+    thread_bound: PhantomData<*const ()>,
+}
+
+impl Drop for ModulusGuard {
+    fn drop(&mut self) {
+        DYNAMIC_MODULUS.with(|barrett| barrett.set(self.previous));
+    }
+}
+
+
+// 法mの剰余類 (0 <= value < mで持ち、掛け算はバレット還元で行う)
+// 法が合成数でも使えるが、割り算はmと互いに素な数でしかできない
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ModIntBase<T> {
+    value: u64,
+    modulus: PhantomData<T>,
+}
+
+// ModInt<1_000_000_007>のように法を型で指定する
+pub type ModInt<const M: u64> = ModIntBase<StaticModulus<M>>;
+
+// 法を実行時に決める
+//   let _guard = DynamicModInt::scoped_modulus(m);
+//   let x = DynamicModInt::new(a);
+// 法はスレッドごと。ガードより長く値を使ったり、入れ子にしたガードの外で値を使ったりしてはいけない
+pub type DynamicModInt = ModIntBase<DynamicModulus>;
+
+impl DynamicModInt {
+    pub fn scoped_modulus(m: u64) -> ModulusGuard {
+        assert!(m != 0, "modulus must be positive");
+        let previous = DYNAMIC_MODULUS.with(|barrett| barrett.replace(Some(Barrett64::new(m))));
+        ModulusGuard { previous, thread_bound: PhantomData }
+    }
+}
+
+impl<T: Modulus> ModIntBase<T> {
+    pub fn new(value: u64) -> Self {
+        Self::from_reduced(value % Self::modulus())
+    }
+
+    fn from_reduced(value: u64) -> Self {
+        Self { value, modulus: PhantomData }
+    }
+
+    pub fn modulus() -> u64 {
+        T::barrett().modulus()
+    }
+
+    pub fn value(&self) -> u64 {
+        self.value
+    }
+
+    pub fn pow(&self, n: u64) -> Self {
+        Self::from_reduced(T::barrett().pow(self.value, n))
+    }
+
+    // 逆元 (法と互いに素でなければNone)
+    pub fn checked_inv(&self) -> Option<Self> {
+        mod_inverse(self.value, Self::modulus()).map(Self::from_reduced)
+    }
+
+    // 逆元 (法と互いに素でなければpanic)
+    pub fn inv(&self) -> Self {
+        self.checked_inv().expect("value is not invertible")
+    }
+}
+
+impl<T: Modulus> Default for ModIntBase<T> {
+    fn default() -> Self {
+        Self::from_reduced(0)
+    }
+}
+
+macro_rules! impl_from_unsigned {
+    ($($t:ty),*) => {
+        $(
+            impl<T: Modulus> From<$t> for ModIntBase<T> {
+                fn from(value: $t) -> Self {
+                    Self::new(value as u64)
+                }
+            }
+        )*
+    };
+}
+
+impl_from_unsigned!(u8, u16, u32, u64, usize);
+
+macro_rules! impl_from_signed {
+    ($($t:ty),*) => {
+        $(
+            impl<T: Modulus> From<$t> for ModIntBase<T> {
+                fn from(value: $t) -> Self {
+                    let m = Self::modulus() as i128;
+                    Self::from_reduced((value as i128).rem_euclid(m) as u64)
+                }
+            }
+        )*
+    };
+}
+
+impl_from_signed!(i32, i64);
+
+impl<T: Modulus> AddAssign for ModIntBase<T> {
+    fn add_assign(&mut self, other: Self) {
+        self.value = addmod(self.value, other.value, Self::modulus());
+    }
+}
+
+impl<T: Modulus> SubAssign for ModIntBase<T> {
+    fn sub_assign(&mut self, other: Self) {
+        self.value = submod(self.value, other.value, Self::modulus());
+    }
+}
+
+impl<T: Modulus> MulAssign for ModIntBase<T> {
+    fn mul_assign(&mut self, other: Self) {
+        self.value = T::barrett().mul(self.value, other.value);
+    }
+}
+
+impl<T: Modulus> DivAssign for ModIntBase<T> {
+    fn div_assign(&mut self, other: Self) {
+        self.mul_assign(other.inv());
+    }
+}
+
+macro_rules! impl_binary_op {
+    ($($op:ident, $method:ident, $op_assign:ident, $method_assign:ident);*) => {
+        $(
+            impl<T: Modulus> $op for ModIntBase<T> {
+                type Output = Self;
+
+                fn $method(mut self, other: Self) -> Self {
+                    $op_assign::$method_assign(&mut self, other);
+                    self
+                }
+            }
+        )*
+    };
+}
+
+impl_binary_op!(
+    Add, add, AddAssign, add_assign;
+    Sub, sub, SubAssign, sub_assign;
+    Mul, mul, MulAssign, mul_assign;
+    Div, div, DivAssign, div_assign
+);
+
+impl<T: Modulus> Neg for ModIntBase<T> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self::default() - self
+    }
+}
+
+impl<T: Modulus> Sum for ModIntBase<T> {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::default(), Add::add)
+    }
+}
+
+impl<'a, T: Modulus> Sum<&'a Self> for ModIntBase<T> {
+    fn sum<I: Iterator<Item = &'a Self>>(iter: I) -> Self {
+        iter.copied().sum()
+    }
+}
+
+impl<T: Modulus> Product for ModIntBase<T> {
+    fn product<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Self::new(1), Mul::mul)
+    }
+}
+
+impl<'a, T: Modulus> Product<&'a Self> for ModIntBase<T> {
+    fn product<I: Iterator<Item = &'a Self>>(iter: I) -> Self {
+        iter.copied().product()
+    }
+}
+
+impl<T> fmt::Display for ModIntBase<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.value)
+    }
+}
+
+impl<T> fmt::Debug for ModIntBase<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.value)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binomial::Binomial;
+    use crate::matrix::Matrix;
+    use crate::multiplicative::multiplicative_sieve;
+
+    type Mint = ModInt<1_000_000_007>;
+
+    #[test]
+    fn test_mod_int() {
+        let a = Mint::new(1_000_000_006);
+        let b = Mint::from(5);
+        assert_eq!(Mint::modulus(), 1_000_000_007);
+        assert_eq!((a + b).value(), 4);
+        assert_eq!((b - a).value(), 6);
+        assert_eq!((a * a).value(), 1);
+        assert_eq!((-b).value(), 1_000_000_002);
+        assert_eq!(-Mint::from(0), Mint::from(0));
+        assert_eq!(Mint::from(-3), Mint::new(1_000_000_004));
+        assert_eq!(Mint::from(u64::MAX).value(), u64::MAX % 1_000_000_007);
+        assert_eq!(b / b, Mint::from(1));
+        assert_eq!(Mint::from(1) / Mint::from(2), Mint::new(500_000_004));
+        assert_eq!(b.inv() * b, Mint::from(1));
+        assert_eq!(Mint::from(0).checked_inv(), None);
+        assert_eq!(Mint::from(2).pow(1_000_000_006), Mint::from(1));
+        assert_eq!(Mint::from(2).pow(0), Mint::from(1));
+
+        let mut x = Mint::from(10);
+        x += Mint::from(5);
+        x -= Mint::from(20);
+        x *= Mint::from(3);
+        x /= Mint::from(5);
+        assert_eq!(x, Mint::from(-3));
+        assert_eq!(format!("{} {:?}", x, x), "1000000004 1000000004");
+
+        assert_eq!((1..=10).map(Mint::from).sum::<Mint>(), Mint::from(55));
+        assert_eq!((1..=20u64).map(Mint::from).product::<Mint>(), Mint::new(146_326_063));
+        let values = [Mint::from(2), Mint::from(3), Mint::from(7)];
+        assert_eq!(values.iter().sum::<Mint>(), Mint::from(12));
+        assert_eq!(values.iter().product::<Mint>(), Mint::from(42));
+        assert_eq!(Vec::<Mint>::new().iter().product::<Mint>(), Mint::from(1));
+
+        // 合成数や2^63以上の法
+        type Mint10 = ModInt<10>;
+        assert_eq!(Mint10::from(7) * Mint10::from(8), Mint10::from(6));
+        assert_eq!(Mint10::from(3).inv(), Mint10::from(7));
+        assert_eq!(Mint10::from(4).checked_inv(), None);
+        type MintLarge = ModInt<18_446_744_073_709_551_557>;
+        let c = MintLarge::new(u64::MAX - 100);
+        assert_eq!((c + c).value(), ((u64::MAX - 100) as u128 * 2 % 18_446_744_073_709_551_557) as u64);
+        assert_eq!(c * c.inv(), MintLarge::from(1));
+        assert_eq!((c - c - c).value(), 18_446_744_073_709_551_557 - (u64::MAX - 100));
+    }
+
+    #[test]
+    fn test_dynamic_mod_int() {
+        let _guard = DynamicModInt::scoped_modulus(998_244_353);
+        assert_eq!(DynamicModInt::modulus(), 998_244_353);
+        let a = DynamicModInt::from(3);
+        assert_eq!(a.pow(998_244_352), DynamicModInt::from(1));
+        assert_eq!((a / DynamicModInt::from(2)).value(), 499_122_178);
+        assert_eq!(DynamicModInt::from(-1).value(), 998_244_352);
+
+        // 法は実行時に決めてよい
+        for m in 1..50 {
+            let _guard = DynamicModInt::scoped_modulus(m);
+            for a in 0..m {
+                let x = DynamicModInt::new(a);
+                for b in 0..m {
+                    let y = DynamicModInt::new(b);
+                    assert_eq!((x + y).value(), (a + b) % m);
+                    assert_eq!((x - y).value(), (a + m - b) % m);
+                    assert_eq!((x * y).value(), a * b % m);
+                }
+                let inverse = (0..m).find(|&b| a * b % m == 1 % m);
+                assert_eq!(x.checked_inv().map(|x| x.value()), inverse);
+            }
+        }
+        assert_eq!(DynamicModInt::modulus(), 998_244_353);
+    }
+
+    #[test]
+    fn test_dynamic_modulus_scope() {
+        // 法を設定していないスレッドではpanicする
+        assert!(std::thread::spawn(DynamicModInt::modulus).join().is_err());
+
+        // ガードを捨てると元の法に戻る
+        {
+            let _outer = DynamicModInt::scoped_modulus(13);
+            assert_eq!((DynamicModInt::new(10) * DynamicModInt::new(10)).value(), 9);
+            {
+                let _inner = DynamicModInt::scoped_modulus(7);
+                assert_eq!((DynamicModInt::new(10) * DynamicModInt::new(10)).value(), 2);
+            }
+            assert_eq!(DynamicModInt::modulus(), 13);
+        }
+        assert!(std::panic::catch_unwind(DynamicModInt::modulus).is_err());
+
+        // 別々の法を使うスレッドは互いに影響しない
+        let handles = (0..4u64)
+            .map(|i| {
+                std::thread::spawn(move || {
+                    let m = [13, 1_000_000_007, 998_244_353, 97][i as usize];
+                    let _guard = DynamicModInt::scoped_modulus(m);
+                    (0..10_000).all(|a| {
+                        let x = DynamicModInt::new(a + i);
+                        (x * x).value() == (a + i) * (a + i) % m
+                    })
+                })
+            })
+            .collect::<Vec<_>>();
+        assert!(handles.into_iter().all(|handle| handle.join().unwrap()));
+    }
+
+    #[test]
+    fn test_generic_use() {
+        // 行列累乗でF(10^18) mod 10^9 + 7
+        let q = Matrix::from_rows(vec![vec![Mint::from(1), Mint::from(1)], vec![Mint::from(1), Mint::from(0)]]);
+        assert_eq!(q.pow(10u64.pow(18))[(0, 1)], Mint::new(209_783_453));
+
+        // 約数の和σ(n) mod 10^9 + 7 (σ(p^k) = (p^(k+1) - 1) / (p - 1))
+        let sigma = multiplicative_sieve(1000, |p, _, pk| (Mint::new(pk) * Mint::new(p) - Mint::from(1)) / Mint::new(p - 1));
+        for n in 1..=1000u64 {
+            let expected = (1..=n).filter(|d| n.is_multiple_of(*d)).sum::<u64>();
+            assert_eq!(sigma[n as usize], Mint::new(expected));
+        }
+
+        // 階乗と二項係数
+        let binomial = Binomial::new(1000, 1_000_000_007);
+        let factorials = (0..=1000u64)
+            .scan(Mint::from(1), |f, n| {
+                if n > 0 {
+                    *f *= Mint::new(n);
+                }
+                Some(*f)
+            })
+            .collect::<Vec<_>>();
+        for n in (0..=1000).step_by(37) {
+            for k in (0..=n).step_by(11) {
+                let c = factorials[n] / (factorials[k] * factorials[n - k]);
+                assert_eq!(c.value(), binomial.binomial(n as u64, k as u64));
+            }
+        }
+    }
+}
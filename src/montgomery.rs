@@ -0,0 +1,331 @@
+// モンゴメリ乗算
+//
+// 奇数mとR = 2^128に対してaをaR mod m(モンゴメリ表現)で持つと、
+//   REDC(T) = T * R^(-1) mod m
+// が割り算なしで計算でき、aR * bR -> REDC(aR * bR) = abR mod mと掛け算できる。
+// 128bit同士の積は256bitになるので上位・下位の128bitの組で扱う。
+#[derive(Clone, Copy, Debug)]
+pub struct Montgomery128 {
+    m: u128,
+    m_inv: u128,        // m * m_inv ≡ 1 (mod R)
+    r: u128,            // R mod m
+    r2: u128,           // R^2 mod m
+}
+
+impl Montgomery128 {
+    pub fn new(m: u128) -> Self {
+        assert!(m % 2 == 1, "modulus must be odd");
+        // ニュートン法: m * x ≡ 1 (mod 2^k) ならば m * x(2 - mx) ≡ 1 (mod 2^2k)
+        let mut m_inv = m;                  // 奇数mについてm * m ≡ 1 (mod 8)
+        for _ in 0..6 {
+            m_inv = m_inv.wrapping_mul(2u128.wrapping_sub(m.wrapping_mul(m_inv)));
+        }
+        let r = 0u128.wrapping_sub(m) % m;
+        let mut r2 = r;
+        for _ in 0..128 {
+            r2 = add_mod(r2, r2, m);
+        }
+        Self { m, m_inv, r, r2 }
+    }
+
+    pub fn modulus(&self) -> u128 {
+        self.m
+    }
+
+    pub fn one(&self) -> u128 {
+        self.r
+    }
+
+    pub fn to_mont(&self, a: u128) -> u128 {
+        self.mul(a % self.m, self.r2)
+    }
+
+    pub fn from_mont(&self, a: u128) -> u128 {
+        self.redc(0, a)
+    }
+
+    // T = hi * R + loに対してT * R^(-1) mod mを求める (T < mR)
+    fn redc(&self, hi: u128, lo: u128) -> u128 {
+        // q = lo * m^(-1) mod Rとすればq * mの下位128bitはloに一致するので
+        // (T - qm) / R = hi - (qmの上位128bit)
+        let q = lo.wrapping_mul(self.m_inv);
+        let (qm_hi, _) = mul_wide(q, self.m);
+        if hi >= qm_hi {
+            hi - qm_hi
+        } else {
+            hi.wrapping_sub(qm_hi).wrapping_add(self.m)
+        }
+    }
+
+    pub fn mul(&self, a: u128, b: u128) -> u128 {
+        let (hi, lo) = mul_wide(a, b);
+        self.redc(hi, lo)
+    }
+
+    pub fn add(&self, a: u128, b: u128) -> u128 {
+        add_mod(a, b, self.m)
+    }
+
+    pub fn sub(&self, a: u128, b: u128) -> u128 {
+        if a >= b { a - b } else { a.wrapping_sub(b).wrapping_add(self.m) }
+    }
+
+    // aはモンゴメリ表現、戻り値もモンゴメリ表現
+    pub fn pow(&self, a: u128, mut n: u128) -> u128 {
+        let mut result = self.r;
+        let mut a = a;
+        while n != 0 {
+            if n & 1 == 1 {
+                result = self.mul(result, a);
+            }
+            a = self.mul(a, a);
+            n >>= 1;
+        }
+        result
+    }
+}
+
+
+
+// Montgomery128のu64版 (R = 2^64)
+// 積がu128に収まるので、REDCもu128の掛け算一回で済む
+#[derive(Clone, Copy, Debug)]
+pub struct Montgomery64 {
+    m: u64,
+    m_inv: u64,         // m * m_inv ≡ 1 (mod R)
+    r: u64,             // R mod m
+    r2: u64,            // R^2 mod m
+}
+
+impl Montgomery64 {
+    pub fn new(m: u64) -> Self {
+        assert!(m % 2 == 1, "modulus must be odd");
+        let mut m_inv = m;
+        for _ in 0..5 {
+            m_inv = m_inv.wrapping_mul(2u64.wrapping_sub(m.wrapping_mul(m_inv)));
+        }
+        let r = 0u64.wrapping_sub(m) % m;
+        let r2 = (r as u128 * r as u128 % m as u128) as u64;
+        Self { m, m_inv, r, r2 }
+    }
+
+    pub fn modulus(&self) -> u64 {
+        self.m
+    }
+
+    pub fn one(&self) -> u64 {
+        self.r
+    }
+
+    pub fn to_mont(&self, a: u64) -> u64 {
+        self.mul(a % self.m, self.r2)
+    }
+
+    pub fn from_mont(&self, a: u64) -> u64 {
+        self.redc(a as u128)
+    }
+
+    // T * R^(-1) mod m (T < mR)
+    fn redc(&self, t: u128) -> u64 {
+        let q = (t as u64).wrapping_mul(self.m_inv);
+        let qm_hi = ((q as u128 * self.m as u128) >> 64) as u64;
+        let hi = (t >> 64) as u64;
+        if hi >= qm_hi {
+            hi - qm_hi
+        } else {
+            hi.wrapping_sub(qm_hi).wrapping_add(self.m)
+        }
+    }
+
+    pub fn mul(&self, a: u64, b: u64) -> u64 {
+        self.redc(a as u128 * b as u128)
+    }
+
+    pub fn add(&self, a: u64, b: u64) -> u64 {
+        if a >= self.m - b { a - (self.m - b) } else { a + b }
+    }
+
+    pub fn sub(&self, a: u64, b: u64) -> u64 {
+        if a >= b { a - b } else { a.wrapping_sub(b).wrapping_add(self.m) }
+    }
+
+    // aはモンゴメリ表現、戻り値もモンゴメリ表現
+    pub fn pow(&self, a: u64, mut n: u64) -> u64 {
+        let mut result = self.r;
+        let mut a = a;
+        while n != 0 {
+            if n & 1 == 1 {
+                result = self.mul(result, a);
+            }
+            a = self.mul(a, a);
+            n >>= 1;
+        }
+        result
+    }
+}
+
+
+// バレット還元
+//
+// mu = floor((2^128 - 1) / m)を前もって求めておき、x < 2^128の商を
+//   q = floor(x * mu / 2^128)
+// で近似する。qは真の商より高々2小さいだけなので、x - qmから何度かmを引けば余りになる。
+// モンゴメリ乗算と違って偶数の法にも使え、値を変換する必要もない。
+#[derive(Clone, Copy, Debug)]
+pub struct Barrett64 {
+    m: u64,
+    mu: u128,
+}
+
+impl Barrett64 {
+    pub const fn new(m: u64) -> Self {
+        assert!(m != 0, "modulus must be positive");
+        Self { m, mu: u128::MAX / m as u128 }
+    }
+
+    pub fn modulus(&self) -> u64 {
+        self.m
+    }
+
+    // x mod m
+    pub fn reduce(&self, x: u128) -> u64 {
+        let (q, _) = mul_wide(x, self.mu);
+        let mut r = x.wrapping_sub(q.wrapping_mul(self.m as u128));
+        while r >= self.m as u128 {
+            r -= self.m as u128;
+        }
+        r as u64
+    }
+
+    // a * b mod m
+    pub fn mul(&self, a: u64, b: u64) -> u64 {
+        self.reduce(a as u128 * b as u128)
+    }
+
+    // a^n mod m
+    pub fn pow(&self, a: u64, mut n: u64) -> u64 {
+        let mut result = self.reduce(1);
+        let mut a = self.reduce(a as u128);
+        while n != 0 {
+            if n & 1 == 1 {
+                result = self.mul(result, a);
+            }
+            a = self.mul(a, a);
+            n >>= 1;
+        }
+        result
+    }
+}
+
+// a, b < mに対して(a + b) mod m (オーバーフローしない)
+fn add_mod(a: u128, b: u128, m: u128) -> u128 {
+    if a >= m - b { a - (m - b) } else { a + b }
+}
+
+
+// 128bit * 128bit -> (上位128bit, 下位128bit)
+pub fn mul_wide(a: u128, b: u128) -> (u128, u128) {
+    const MASK: u128 = u64::MAX as u128;
+    let (a_hi, a_lo) = (a >> 64, a & MASK);
+    let (b_hi, b_lo) = (b >> 64, b & MASK);
+
+    let lo_lo = a_lo * b_lo;
+    let hi_lo = a_hi * b_lo;
+    let lo_hi = a_lo * b_hi;
+    let hi_hi = a_hi * b_hi;
+
+    // 中間の桁は繰り上がりが出ないように64bitずつ分けて足す
+    let mid = (lo_lo >> 64) + (hi_lo & MASK) + (lo_hi & MASK);
+    let lo = (mid << 64) | (lo_lo & MASK);
+    let hi = hi_hi + (hi_lo >> 64) + (lo_hi >> 64) + (mid >> 64);
+    (hi, lo)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mul_wide() {
+        assert_eq!(mul_wide(0, u128::MAX), (0, 0));
+        assert_eq!(mul_wide(1 << 64, 1 << 64), (1, 0));
+        assert_eq!(mul_wide(u128::MAX, u128::MAX), (u128::MAX - 1, 1));
+        assert_eq!(mul_wide(u64::MAX as u128, u64::MAX as u128), (0, (u64::MAX as u128).pow(2)));
+    }
+
+    #[test]
+    fn test_montgomery64() {
+        let moduli = &[1, 3, 1_000_000_007, (1 << 61) - 1, 18_446_744_073_709_551_557, u64::MAX];
+        let values = &[0, 1, 2, 12345, 1 << 40, u64::MAX - 1, u64::MAX];
+        for &m in moduli {
+            let mont = Montgomery64::new(m);
+            let m128 = m as u128;
+            for &a in values {
+                let a_mont = mont.to_mont(a);
+                assert_eq!(mont.from_mont(a_mont), a % m);
+                for &b in values {
+                    let b_mont = mont.to_mont(b);
+                    let expected = (a as u128 % m128 * (b as u128 % m128) % m128) as u64;
+                    assert_eq!(mont.from_mont(mont.mul(a_mont, b_mont)), expected);
+                    let sum = ((a % m) as u128 + (b % m) as u128) % m128;
+                    assert_eq!(mont.from_mont(mont.add(a_mont, b_mont)), sum as u64);
+                    assert_eq!(mont.from_mont(mont.sub(mont.add(a_mont, b_mont), b_mont)), a % m);
+                }
+            }
+        }
+        let p = 18_446_744_073_709_551_557;
+        let mont = Montgomery64::new(p);
+        assert_eq!(mont.from_mont(mont.pow(mont.to_mont(2), p - 1)), 1);
+        assert_eq!(mont.from_mont(mont.pow(mont.to_mont(3), 0)), 1);
+    }
+
+    #[test]
+    fn test_barrett64() {
+        let moduli = &[1, 2, 3, 10, 1 << 32, 1_000_000_007, 1 << 63, 18_446_744_073_709_551_557, u64::MAX];
+        let values = &[0, 1, 2, 12345, 1 << 40, u64::MAX - 1, u64::MAX];
+        for &m in moduli {
+            let barrett = Barrett64::new(m);
+            let m128 = m as u128;
+            for &a in values {
+                for &b in values {
+                    let x = a as u128 * b as u128;
+                    assert_eq!(barrett.reduce(x), (x % m128) as u64);
+                    assert_eq!(barrett.mul(a % m, b % m), (a as u128 % m128 * (b as u128 % m128) % m128) as u64);
+                }
+            }
+            assert_eq!(barrett.reduce(u128::MAX), (u128::MAX % m128) as u64);
+        }
+        assert_eq!(Barrett64::new(1 << 40).pow(3, 1 << 38), 1);
+        assert_eq!(Barrett64::new(1_000_000_007).pow(2, 1_000_000_006), 1);
+        assert_eq!(Barrett64::new(1).pow(5, 0), 0);
+    }
+
+    #[test]
+    fn test_montgomery128() {
+        let moduli = &[3, 1_000_000_007, u64::MAX as u128, (1 << 127) - 1, u128::MAX];
+        let values = &[0, 1, 2, 12345, u64::MAX as u128, 1 << 100, u128::MAX - 1];
+        for &m in moduli {
+            let mont = Montgomery128::new(m);
+            for &a in values {
+                let a_mont = mont.to_mont(a);
+                assert_eq!(mont.from_mont(a_mont), a % m);
+                for &b in values {
+                    let b_mont = mont.to_mont(b);
+                    let product = mont.from_mont(mont.mul(a_mont, b_mont));
+                    if m <= u64::MAX as u128 {
+                        assert_eq!(product, (a % m) * (b % m) % m);
+                    }
+                    assert_eq!(mont.from_mont(mont.add(a_mont, b_mont)), add_mod(a % m, b % m, m));
+                    assert_eq!(mont.from_mont(mont.sub(mont.add(a_mont, b_mont), b_mont)), a % m);
+                }
+            }
+        }
+
+        // フェルマーの小定理: 2^(p-1) ≡ 1 (mod p)
+        let p = (1u128 << 127) - 1;
+        let mont = Montgomery128::new(p);
+        assert_eq!(mont.from_mont(mont.pow(mont.to_mont(2), p - 1)), 1);
+        assert_eq!(mont.from_mont(mont.pow(mont.to_mont(3), 0)), 1);
+    }
+}
@@ -0,0 +1,129 @@
+use std::ops::Mul;
+
+
+// 素数冪での値f(p, k, p^k)だけから乗法的関数の0..=limitでの値を求める
+//
+// 線形篩でnの最小素因数pとそれを割り切る最大のp冪p^kを記録しておけば
+//   g(n) = g(n / p^k) * f(p, k, p^k)
+// とnより小さい値から順に求まる。(g(0)は0、g(1)は1とする)
+pub fn multiplicative_sieve<T, F>(limit: usize, f: F) -> Vec<T>
+where
+    T: Clone + Mul<Output = T> + From<u8>,
+    F: Fn(u64, u32, u64) -> T,
+{
+    let mut values = Vec::with_capacity(limit + 1);
+    values.push(T::from(0));
+    if limit == 0 {
+        return values;
+    }
+    values.push(T::from(1));
+
+    let mut primes: Vec<usize> = Vec::new();
+    let mut least_prime = vec![0; limit + 1];       // nの最小素因数p
+    let mut prime_power = vec![0; limit + 1];       // nを割り切る最大のp冪p^k
+    let mut exponent = vec![0u32; limit + 1];       // k
+
+    for n in 2..=limit {
+        if least_prime[n] == 0 {
+            least_prime[n] = n;
+            prime_power[n] = n;
+            exponent[n] = 1;
+            primes.push(n);
+        }
+        let value = if prime_power[n] == n {
+            f(least_prime[n] as u64, exponent[n], n as u64)
+        } else {
+            values[n / prime_power[n]].clone() * values[prime_power[n]].clone()
+        };
+        values.push(value);
+
+        // 最小素因数がpとなるn * pにだけ印をつけるので各合成数はちょうど一回ずつ処理される
+        for &p in primes.iter() {
+            if p > least_prime[n] || n * p > limit {
+                break;
+            }
+            let m = n * p;
+            least_prime[m] = p;
+            if p == least_prime[n] {
+                prime_power[m] = prime_power[n] * p;
+                exponent[m] = exponent[n] + 1;
+            } else {
+                prime_power[m] = p;
+                exponent[m] = 1;
+            }
+        }
+    }
+    values
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::divisors::{divisor_count_sieve, sigma_k_sieve};
+    use crate::mobius::mobius_sieve;
+    use crate::totient::totient_sieve;
+
+    const LIMIT: usize = 10000;
+
+    #[test]
+    fn test_integer_functions() {
+        let phi = multiplicative_sieve(LIMIT, |p, _, p_k| p_k / p * (p - 1));
+        assert_eq!(phi, totient_sieve(LIMIT));
+
+        let mu = multiplicative_sieve(LIMIT, |_, k, _| if k == 1 { -1i64 } else { 0 });
+        let expected = mobius_sieve(LIMIT).iter().map(|&x| x as i64).collect::<Vec<_>>();
+        assert_eq!(mu, expected);
+
+        let d = multiplicative_sieve(LIMIT, |_, k, _| k as u64 + 1);
+        assert_eq!(d, divisor_count_sieve(LIMIT));
+
+        let sigma_2 = multiplicative_sieve(LIMIT, |p, _, p_k| (p_k * p_k * p * p - 1) / (p * p - 1));
+        assert_eq!(sigma_2, sigma_k_sieve(LIMIT, 2));
+    }
+
+    #[test]
+    fn test_small_limits() {
+        assert_eq!(multiplicative_sieve(0, |_, k, _| k as u64 + 1), vec![0]);
+        assert_eq!(multiplicative_sieve(1, |_, k, _| k as u64 + 1), vec![0, 1]);
+        assert_eq!(multiplicative_sieve(4, |_, k, _| k as u64 + 1), vec![0, 1, 2, 2, 3]);
+    }
+
+    #[test]
+    fn test_modular_values() {
+        const M: u64 = 1_000_000_007;
+
+        #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+        struct Mod(u64);
+
+        impl From<u8> for Mod {
+            fn from(x: u8) -> Self {
+                Mod(x as u64)
+            }
+        }
+
+        impl Mul for Mod {
+            type Output = Mod;
+
+            fn mul(self, rhs: Self) -> Self::Output {
+                Mod(self.0 * rhs.0 % M)
+            }
+        }
+
+        // σ_3(n) mod M
+        let sigma_3 = multiplicative_sieve(LIMIT, |p, k, _| {
+            let p_3 = p.pow(3) % M;
+            let mut term = 1;
+            let mut p_3_pow = 1;
+            for _ in 0..k {
+                p_3_pow = p_3_pow * p_3 % M;
+                term = (term + p_3_pow) % M;
+            }
+            Mod(term)
+        });
+        let expected = sigma_k_sieve(LIMIT, 3);
+        for n in 1..=LIMIT {
+            assert_eq!(sigma_3[n], Mod(expected[n] % M));
+        }
+    }
+}
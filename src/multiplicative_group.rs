@@ -0,0 +1,249 @@
+use crate::prime::factorize;
+use crate::totient::totient;
+use crate::utils::{crt, gcd, isqrt, mod_inverse, modpow, mulmod};
+use std::collections::HashMap;
+
+
+// カーマイケル関数λ(n): すべてのgcd(a, n) = 1なるaについてa^λ(n) ≡ 1 (mod n)となる最小の正整数
+//   λ(2) = 1, λ(4) = 2, λ(2^k) = 2^(k-2) (k >= 3)
+//   λ(p^k) = p^(k-1)(p - 1)
+//   λ(n) = lcm(λ(p_i^k_i))
+pub fn carmichael_lambda(n: u64) -> u64 {
+    factorize(n).iter().fold(1, |acc, &(p, k)| {
+        let lambda = match (p, k) {
+            (2, 1) => 1,
+            (2, 2) => 2,
+            (2, _) => 1 << (k - 2),
+            _ => p.pow(k - 1) * (p - 1),
+        };
+        acc / gcd(acc, lambda) * lambda
+    })
+}
+
+
+// aの位数ord_n(a): a^k ≡ 1 (mod n)となる最小の正整数k
+// 位数はλ(n)の約数なので、λ(n)から各素因数を割れるだけ割っていく
+pub fn multiplicative_order(a: u64, n: u64) -> Option<u64> {
+    if gcd(a % n, n) != 1 {
+        return None;
+    }
+    if n == 1 {
+        return Some(1);
+    }
+    let lambda = carmichael_lambda(n);
+    let mut order = lambda;
+    for (q, _) in factorize(lambda) {
+        while order.is_multiple_of(q) && modpow(a, order / q, n) == 1 {
+            order /= q;
+        }
+    }
+    Some(order)
+}
+
+
+// gがnの原始根かどうか (位数がφ(n)と一致するか)
+pub fn is_primitive_root(g: u64, n: u64) -> bool {
+    if gcd(g % n, n) != 1 {
+        return false;
+    }
+    let phi = totient(n);
+    factorize(phi).iter().all(|&(q, _)| modpow(g, phi / q, n) != 1)
+}
+
+
+// 最小の原始根
+// 原始根が存在するのはn = 1, 2, 4, p^k, 2p^k (pは奇素数)のときだけ
+pub fn primitive_root(n: u64) -> Option<u64> {
+    let factors = factorize(n);
+    let has_primitive_root = match factors[..] {
+        [] | [(2, 1)] | [(2, 2)] => true,
+        [(p, _)] | [(2, 1), (p, _)] => p != 2,
+        _ => false,
+    };
+    if !has_primitive_root {
+        return None;
+    }
+    if n <= 2 {
+        return Some(n - 1);
+    }
+    let phi = totient(n);
+    let phi_factors = factorize(phi);
+    (2..n).find(|&g| gcd(g, n) == 1 && phi_factors.iter().all(|&(q, _)| modpow(g, phi / q, n) != 1))
+}
+
+
+// 離散対数: a^x ≡ b (mod n)となる最小のx >= 0
+//
+// gcd(a, n) > 1のときは両辺をgcdで割ってaとnが互いに素な問題に帰着させる。
+// 互いに素なときはaの位数の素因数分解を使ってPohlig-Hellmanで各素数冪ごとに解き、
+// 各桁は小さな部分群でbaby-step giant-stepにより求める。
+pub fn discrete_log(a: u64, b: u64, n: u64) -> Option<u64> {
+    if n == 1 {
+        return Some(0);
+    }
+    let mut a = a % n;
+    let mut b = b % n;
+    let mut n = n;
+
+    // coef * a^(x - k) ≡ b (mod n)
+    let mut k = 0;
+    let mut coef = 1 % n;
+    loop {
+        if coef == b {
+            return Some(k);
+        }
+        let g = gcd(a, n);
+        if g == 1 {
+            break;
+        }
+        if !b.is_multiple_of(g) {
+            return None;
+        }
+        b /= g;
+        n /= g;
+        coef = mulmod(coef, a / g, n);
+        a %= n;
+        k += 1;
+    }
+
+    // a^y ≡ b * coef^(-1) (mod n)
+    let coef_inv = mod_inverse(coef, n)?;
+    let target = mulmod(b, coef_inv, n);
+    discrete_log_coprime(a, target, n).map(|y| y + k)
+}
+
+
+// gcd(a, n) = 1のときの離散対数 (Pohlig-Hellman)
+fn discrete_log_coprime(a: u64, b: u64, n: u64) -> Option<u64> {
+    let order = multiplicative_order(a, n)?;
+    // x mod orderを各素数冪q^eごとに求めて中国剰余定理で合わせる
+    let mut congruences = Vec::new();
+    for (q, e) in factorize(order) {
+        let x_q = discrete_log_prime_power(a, b, n, order, q, e)?;
+        congruences.push((x_q, q.pow(e)));
+    }
+    let (x, _) = crt(&congruences)?;
+    if modpow(a, x, n) == b % n { Some(x) } else { None }
+}
+
+
+// x mod q^eを求める (orderはaの位数でq^eで割り切れる)
+// x = d_0 + d_1q + ... + d_(e-1)q^(e-1)として
+//   (a^(-(d_0 + ... + d_(i-1)q^(i-1))) * b)^(order/q^(i+1)) = γ^(d_i), γ = a^(order/q)
+// からd_iを下の桁から順に求める
+fn discrete_log_prime_power(a: u64, b: u64, n: u64, order: u64, q: u64, e: u32) -> Option<u64> {
+    let gamma = modpow(a, order / q, n);
+    let mut x = 0;
+    let mut q_i = 1;
+    for i in 0..e {
+        let a_inv_x = modpow(a, (order - x % order) % order, n);
+        let h = modpow(mulmod(a_inv_x, b, n), order / q_i / q, n);
+        let d = baby_step_giant_step(gamma, h, q, n)?;
+        x += d * q_i;
+        if i + 1 < e {
+            q_i *= q;
+        }
+    }
+    Some(x)
+}
+
+
+// g^x ≡ h (mod n)となる0 <= x < orderを√order程度の手間で求める (orderはgの位数)
+// x = im + jとしてg^jを表にしておき、h * (g^(-m))^iが表にあるか調べる
+pub fn baby_step_giant_step(g: u64, h: u64, order: u64, n: u64) -> Option<u64> {
+    // m = ⌈√order⌉
+    let mut m = isqrt(order);
+    if m * m < order {
+        m += 1;
+    }
+    let mut table = HashMap::new();
+    let mut g_j = 1 % n;
+    for j in 0..m {
+        table.entry(g_j).or_insert(j);
+        g_j = mulmod(g_j, g, n);
+    }
+    let g_inv_m = modpow(g, (order - m % order) % order, n);
+    let mut gamma = h % n;
+    for i in 0..m {
+        if let Some(&j) = table.get(&gamma) {
+            return Some(i * m + j);
+        }
+        gamma = mulmod(gamma, g_inv_m, n);
+    }
+    None
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn brute_force_order(a: u64, n: u64) -> Option<u64> {
+        if gcd(a, n) != 1 {
+            return None;
+        }
+        (1..=n).find(|&k| modpow(a, k, n) == 1 % n)
+    }
+
+    #[test]
+    fn test_carmichael_lambda() {
+        let test_cases = &[(1, 1), (2, 1), (4, 2), (8, 2), (16, 4), (9, 6), (15, 4), (561, 80), (1_000_000, 50000)];
+        for &(n, expected) in test_cases {
+            assert_eq!(carmichael_lambda(n), expected);
+        }
+        for n in 2..300 {
+            let max_order = (1..n).filter_map(|a| brute_force_order(a, n)).max().unwrap();
+            assert_eq!(carmichael_lambda(n), max_order);
+        }
+    }
+
+    #[test]
+    fn test_multiplicative_order() {
+        for n in 1..300 {
+            for a in 0..n {
+                assert_eq!(multiplicative_order(a, n), brute_force_order(a, n), "{} {}", a, n);
+            }
+        }
+        // 1/7の循環節の長さ
+        assert_eq!(multiplicative_order(10, 7), Some(6));
+        assert_eq!(multiplicative_order(10, 983), Some(982));
+        assert_eq!(multiplicative_order(2, 1_000_000_007), Some(500_000_003));
+    }
+
+    #[test]
+    fn test_primitive_root() {
+        for n in 1..300 {
+            let expected = (0..n).find(|&g| brute_force_order(g, n) == Some(totient(n)));
+            assert_eq!(primitive_root(n), expected, "{}", n);
+            for g in 0..n {
+                assert_eq!(is_primitive_root(g, n), brute_force_order(g, n) == Some(totient(n)));
+            }
+        }
+        assert_eq!(primitive_root(1_000_000_007), Some(5));
+        assert_eq!(primitive_root(998_244_353), Some(3));
+        assert_eq!(primitive_root(2 * 3_u64.pow(10)), Some(5));
+    }
+
+    #[test]
+    fn test_discrete_log() {
+        for n in 1..64 {
+            for a in 0..n {
+                for b in 0..n {
+                    let power = |x| if x == 0 { 1 % n } else { modpow(a, x, n) };
+                    let expected = (0..2 * n).find(|&x| power(x) == b % n);
+                    assert_eq!(discrete_log(a, b, n), expected, "{}^x = {} mod {}", a, b, n);
+                }
+            }
+        }
+        let p = 1_000_000_007;
+        for &b in &[2, 3, 123_456_789, p - 1] {
+            let x = discrete_log(5, b, p).unwrap();
+            assert_eq!(modpow(5, x, p), b);
+        }
+        assert_eq!(discrete_log(5, modpow(5, 987_654_321, p), p), Some(987_654_321));
+        // 位数が大きな素数の部分群にない
+        assert_eq!(discrete_log(4, 5, p), None);
+        assert_eq!(discrete_log(2, 0, 1 << 40), Some(40));
+        assert_eq!(discrete_log(6, 4, 1 << 20), None);
+    }
+}
@@ -0,0 +1,148 @@
+use crate::prime::{sieve_of_eratosthenes, sieve_segment};
+use crate::utils::isqrt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+
+// 複数スレッドによる区間篩
+//
+// [a, b]をsegment_sizeごとの区間に分け、各スレッドが未処理の区間を一つずつ取って篩う。
+// 区間の分け方はスレッド数によらないので、区間ごとの結果を区間の順に並べれば
+// スレッド数によらず同じ結果になる。
+pub struct ParallelSieve {
+    threads: usize,
+    segment_size: u64,
+}
+
+impl ParallelSieve {
+    const DEFAULT_SEGMENT_SIZE: u64 = 1 << 18;
+
+    pub fn new(threads: usize) -> Self {
+        Self::with_segment_size(threads, Self::DEFAULT_SEGMENT_SIZE)
+    }
+
+    pub fn with_segment_size(threads: usize, segment_size: u64) -> Self {
+        assert!(threads > 0, "threads must be positive");
+        assert!(segment_size > 0, "segment_size must be positive");
+        Self { threads, segment_size }
+    }
+
+    // 各区間の素数(昇順)をfに渡し、その戻り値を区間の順に並べて返す
+    // fの引数は(区間の下端, 区間の上端, 区間内の素数)
+    pub fn map_segments<T, F>(&self, a: u64, b: u64, f: F) -> Vec<T>
+    where
+        T: Send,
+        F: Fn(u64, u64, &[u64]) -> T + Sync,
+    {
+        let a = a.max(2);
+        if a > b {
+            return Vec::new();
+        }
+        let segments = self.segments(a, b);
+        let base_primes = sieve_of_eratosthenes(isqrt(b) as usize);
+        let next_segment = AtomicUsize::new(0);
+        let results = Mutex::new((0..segments.len()).map(|_| None).collect::<Vec<Option<T>>>());
+
+        thread::scope(|scope| {
+            for _ in 0..self.threads.min(segments.len()) {
+                scope.spawn(|| {
+                    let mut is_composite = Vec::new();
+                    let mut primes = Vec::new();
+                    loop {
+                        let index = next_segment.fetch_add(1, Ordering::Relaxed);
+                        let Some(&(low, high)) = segments.get(index) else {
+                            break;
+                        };
+                        primes.clear();
+                        sieve_segment(low, high, &base_primes, &mut is_composite, &mut primes);
+                        let result = f(low, high, &primes);
+                        results.lock().unwrap()[index] = Some(result);
+                    }
+                });
+            }
+        });
+
+        results.into_inner().unwrap().into_iter().map(Option::unwrap).collect()
+    }
+
+    // 各区間の素数をfに渡す (呼び出される順序は不定)
+    pub fn for_each_segment<F>(&self, a: u64, b: u64, f: F)
+    where
+        F: Fn(u64, u64, &[u64]) + Sync,
+    {
+        self.map_segments(a, b, f);
+    }
+
+    // a以上b以下の素数の個数
+    pub fn count(&self, a: u64, b: u64) -> u64 {
+        self.map_segments(a, b, |_, _, primes| primes.len() as u64).iter().sum()
+    }
+
+    // a以上b以下の素数の和
+    pub fn sum(&self, a: u64, b: u64) -> u128 {
+        self.map_segments(a, b, |_, _, primes| primes.iter().map(|&p| p as u128).sum::<u128>())
+            .iter()
+            .sum()
+    }
+
+    fn segments(&self, a: u64, b: u64) -> Vec<(u64, u64)> {
+        let mut segments = Vec::new();
+        let mut low = a;
+        loop {
+            let high = b.min(low.saturating_add(self.segment_size - 1));
+            segments.push((low, high));
+            if high == b {
+                return segments;
+            }
+            low = high + 1;
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prime::primes_in_range;
+    use std::sync::atomic::AtomicU64;
+
+    #[test]
+    fn test_count_and_sum() {
+        let primes = primes_in_range(0, 1_000_000);
+        let expected_sum = primes.iter().map(|&p| p as u128).sum::<u128>();
+        for threads in 1..=8 {
+            let sieve = ParallelSieve::with_segment_size(threads, 10_000);
+            assert_eq!(sieve.count(0, 1_000_000), 78498);
+            assert_eq!(sieve.sum(0, 1_000_000), expected_sum);
+        }
+        assert_eq!(ParallelSieve::new(4).sum(0, 2_000_000), 142_913_828_922);
+        assert_eq!(ParallelSieve::new(4).count(0, 1), 0);
+        assert_eq!(ParallelSieve::new(4).count(2, 2), 1);
+        assert_eq!(ParallelSieve::new(4).count(10, 9), 0);
+    }
+
+    #[test]
+    fn test_map_segments() {
+        let a = 1_000_000_000;
+        let b = a + 500_000;
+        let expected = primes_in_range(a, b);
+        for threads in &[1, 3, 8] {
+            let segments = ParallelSieve::with_segment_size(*threads, 12_345)
+                .map_segments(a, b, |low, high, primes| {
+                    assert!(primes.iter().all(|&p| low <= p && p <= high));
+                    primes.to_vec()
+                });
+            assert_eq!(segments.concat(), expected);
+        }
+    }
+
+    #[test]
+    fn test_for_each_segment() {
+        let count = AtomicU64::new(0);
+        ParallelSieve::with_segment_size(4, 1000).for_each_segment(0, 100_000, |_, _, primes| {
+            count.fetch_add(primes.len() as u64, Ordering::Relaxed);
+        });
+        assert_eq!(count.into_inner(), 9592);
+    }
+}
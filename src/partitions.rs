@@ -0,0 +1,95 @@
+use crate::prime::{sieve_of_eratosthenes, PrimeIterator};
+
+
+// nをsetの要素の和で表す方法の数 (順序は区別せず、同じ要素を何度使ってもよい)
+// 要素を一つずつ追加しながら ways[s] += ways[s - x] と数える
+// u64に収まらなければNone (途中の値が溢れたらそれを足し込んだ値も溢れるので、Noneのまま伝える)
+pub fn count_sums_of(set: &[u64], n: u64) -> Option<u64> {
+    let n = n as usize;
+    let mut ways = vec![Some(0u64); n + 1];
+    ways[0] = Some(1);
+    let mut elements = set.to_vec();
+    elements.sort_unstable();
+    elements.dedup();
+    for x in elements.into_iter().filter(|&x| x > 0).map(|x| x as usize) {
+        for s in x..=n {
+            ways[s] = match (ways[s], ways[s - x]) {
+                (Some(a), Some(b)) => a.checked_add(b),
+                _ => None,
+            };
+        }
+    }
+    ways[n]
+}
+
+
+// nを素数の和で表す方法の数 (u64に収まらなければNone)
+pub fn prime_partitions(n: u64) -> Option<u64> {
+    let primes = PrimeIterator::new().take_while(|&p| p <= n).collect::<Vec<_>>();
+    count_sums_of(&primes, n)
+}
+
+
+// n = p + q (p, qは素数)と表す方法の数
+// 戻り値は(順序を区別する場合, 順序を区別しない場合)
+pub fn goldbach_count(n: u64) -> (u64, u64) {
+    let primes = sieve_of_eratosthenes(n as usize);
+    let mut is_prime = vec![false; n as usize + 1];
+    for &p in primes.iter() {
+        is_prime[p as usize] = true;
+    }
+    let unordered = primes.iter()
+        .take_while(|&&p| 2 * p <= n)
+        .filter(|&&p| is_prime[(n - p) as usize])
+        .count() as u64;
+    // p = qのものは順序を入れ替えても同じ
+    let same = n.is_multiple_of(2) && is_prime[n as usize / 2];
+    let ordered = 2 * unordered - same as u64;
+    (ordered, unordered)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_count_sums_of() {
+        // 200ペンスを硬貨で支払う方法
+        assert_eq!(count_sums_of(&[1, 2, 5, 10, 20, 50, 100, 200], 200), Some(73682));
+        assert_eq!(count_sums_of(&[1, 2, 2, 5], 5), Some(4));
+        assert_eq!(count_sums_of(&[3, 5], 0), Some(1));
+        assert_eq!(count_sums_of(&[3, 5], 7), Some(0));
+        assert_eq!(count_sums_of(&[], 7), Some(0));
+        // 分割数p(n)
+        let all = (1..=500).collect::<Vec<_>>();
+        assert_eq!(count_sums_of(&all[..100], 100), Some(190_569_292));
+        // p(416) < 2^64 < p(417)
+        assert_eq!(count_sums_of(&all, 416), Some(17_873_792_969_689_876_004));
+        assert_eq!(count_sums_of(&all, 417), None);
+        assert_eq!(count_sums_of(&all, 500), None);
+    }
+
+    #[test]
+    fn test_prime_partitions() {
+        assert_eq!(prime_partitions(10), Some(5));
+        let expected = [1, 0, 1, 1, 1, 2, 2, 3, 3, 4, 5, 6, 7, 9, 10, 12, 14, 17, 19, 23];
+        for (n, &ways) in expected.iter().enumerate() {
+            assert_eq!(prime_partitions(n as u64), Some(ways));
+        }
+        assert_eq!((0..).find(|&n| prime_partitions(n).unwrap() > 5000), Some(71));
+    }
+
+    #[test]
+    fn test_goldbach_count() {
+        assert_eq!(goldbach_count(4), (1, 1));
+        assert_eq!(goldbach_count(10), (3, 2));
+        assert_eq!(goldbach_count(13), (2, 1));
+        assert_eq!(goldbach_count(11), (0, 0));
+        assert_eq!(goldbach_count(100), (12, 6));
+        assert_eq!(goldbach_count(1), (0, 0));
+        for n in (4..=2000).step_by(2) {
+            assert!(goldbach_count(n).1 > 0);
+        }
+    }
+}
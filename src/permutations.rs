@@ -0,0 +1,316 @@
+// 辞書順で次の順列に並べ替える
+// 最後の順列(降順)だったときは最初の順列(昇順)に戻してfalseを返す
+// 同じ値を含んでいても、異なる並びだけを順に辿る
+pub fn next_permutation<T: Ord>(items: &mut [T]) -> bool {
+    // items[i] < items[i + 1]となる最大のi
+    let i = match items.windows(2).rposition(|w| w[0] < w[1]) {
+        Some(i) => i,
+        None => {
+            items.reverse();
+            return false;
+        }
+    };
+    // i + 1以降は降順なので、items[i]より大きい最も右の要素と入れ替えて、i + 1以降を昇順にする
+    let j = items.iter().rposition(|x| *x > items[i]).unwrap();
+    items.swap(i, j);
+    items[i + 1..].reverse();
+    true
+}
+
+
+// 辞書順で前の順列に並べ替える
+// 最初の順列(昇順)だったときは最後の順列(降順)に戻してfalseを返す
+pub fn prev_permutation<T: Ord>(items: &mut [T]) -> bool {
+    let i = match items.windows(2).rposition(|w| w[0] > w[1]) {
+        Some(i) => i,
+        None => {
+            items.reverse();
+            return false;
+        }
+    };
+    let j = items.iter().rposition(|x| *x < items[i]).unwrap();
+    items.swap(i, j);
+    items[i + 1..].reverse();
+    true
+}
+
+
+// itemsからk個選んで並べたものを、位置の辞書順にすべて返すイテレータ
+// 同じ値があっても位置が違えば別のものとして扱う
+pub struct Permutations<T> {
+    items: Vec<T>,
+    k: usize,
+    indices: Vec<usize>,
+    done: bool,
+}
+
+impl<T: Clone> Iterator for Permutations<T> {
+    type Item = Vec<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let result = self.indices[..self.k].iter().map(|&i| self.items[i].clone()).collect();
+        // k番目以降は常に昇順なので、逆順(最大)にしてから次の順列に進めると先頭k個が変わる
+        self.indices[self.k..].reverse();
+        self.done = !next_permutation(&mut self.indices);
+        Some(result)
+    }
+}
+
+pub fn permutations<T: Clone>(items: &[T], k: usize) -> Permutations<T> {
+    Permutations {
+        items: items.to_vec(),
+        k,
+        indices: (0..items.len()).collect(),
+        done: k > items.len(),
+    }
+}
+
+
+// itemsの並べ替えのうち異なるものを辞書順にすべて返すイテレータ (同じ値は区別しない)
+pub struct MultisetPermutations<T> {
+    current: Vec<T>,
+    done: bool,
+}
+
+impl<T: Ord + Clone> Iterator for MultisetPermutations<T> {
+    type Item = Vec<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let result = self.current.clone();
+        self.done = !next_permutation(&mut self.current);
+        Some(result)
+    }
+}
+
+pub fn multiset_permutations<T: Ord + Clone>(items: &[T]) -> MultisetPermutations<T> {
+    let mut current = items.to_vec();
+    current.sort();
+    MultisetPermutations { current, done: false }
+}
+
+
+// itemsからk個選んだ組合せを、位置の辞書順にすべて返すイテレータ
+pub struct Combinations<T> {
+    items: Vec<T>,
+    indices: Vec<usize>,
+    done: bool,
+}
+
+impl<T: Clone> Iterator for Combinations<T> {
+    type Item = Vec<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let result = self.indices.iter().map(|&i| self.items[i].clone()).collect();
+        // まだ増やせる最も右の添字を一つ増やし、その右を詰めて並べ直す
+        let (n, k) = (self.items.len(), self.indices.len());
+        match (0..k).rposition(|i| self.indices[i] < n - k + i) {
+            Some(i) => {
+                self.indices[i] += 1;
+                for j in i + 1..k {
+                    self.indices[j] = self.indices[j - 1] + 1;
+                }
+            }
+            None => self.done = true,
+        }
+        Some(result)
+    }
+}
+
+pub fn combinations<T: Clone>(items: &[T], k: usize) -> Combinations<T> {
+    Combinations {
+        items: items.to_vec(),
+        indices: (0..k).collect(),
+        done: k > items.len(),
+    }
+}
+
+
+// 0!, 1!, ..., (n-1)! (u64に収まらないものはNone)
+fn factorials(n: usize) -> Vec<Option<u64>> {
+    let mut result = vec![Some(1u64); n.max(1)];
+    for i in 1..n {
+        result[i] = result[i - 1].and_then(|f| f.checked_mul(i as u64));
+    }
+    result
+}
+
+
+// itemsの並べ替えのうち、位置の辞書順でindex番目(0始まり)のもの
+// 階乗進法: index = ∑d_i * (n - 1 - i)! (0 <= d_i <= n - 1 - i)として、
+// 先頭から順に残りの要素のうちd_i番目を選ぶ
+// index >= n!ならNone
+pub fn nth_permutation<T: Clone>(items: &[T], index: u64) -> Option<Vec<T>> {
+    let n = items.len();
+    let factorials = factorials(n + 1);
+    if factorials[n].is_some_and(|f| index >= f) {
+        return None;
+    }
+    let mut remaining = items.to_vec();
+    let mut index = index;
+    let mut result = Vec::with_capacity(n);
+    for i in (0..n).rev() {
+        // i!がu64に収まらなければindex < i!なので桁は0
+        let d = match factorials[i] {
+            Some(f) => {
+                let d = index / f;
+                index %= f;
+                d as usize
+            }
+            None => 0,
+        };
+        result.push(remaining.remove(d));
+    }
+    Some(result)
+}
+
+
+// permが要素を昇順に並べたものから辞書順で何番目(0始まり)の順列か (要素は相異なり、20個以下)
+// 各位置について、それより右にあるより小さい要素の数が階乗進法の各桁になる
+pub fn permutation_rank<T: Ord>(perm: &[T]) -> u64 {
+    let n = perm.len();
+    assert!(n <= 20, "rank must fit in u64");
+    let factorials = factorials(n);
+    (0..n)
+        .map(|i| {
+            let smaller = perm[i + 1..].iter().filter(|&x| *x < perm[i]).count() as u64;
+            smaller * factorials[n - 1 - i].unwrap()
+        })
+        .sum()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prime::is_prime;
+
+    #[test]
+    fn test_next_prev_permutation() {
+        let mut items = [1, 2, 3];
+        let mut all = vec![items.to_vec()];
+        while next_permutation(&mut items) {
+            all.push(items.to_vec());
+        }
+        let expected = vec![[1, 2, 3], [1, 3, 2], [2, 1, 3], [2, 3, 1], [3, 1, 2], [3, 2, 1]];
+        assert_eq!(all, expected);
+        assert_eq!(items, [1, 2, 3]);
+
+        let mut items = [3, 2, 1];
+        let mut all = vec![items.to_vec()];
+        while prev_permutation(&mut items) {
+            all.push(items.to_vec());
+        }
+        all.reverse();
+        assert_eq!(all, expected);
+        assert_eq!(items, [3, 2, 1]);
+
+        // 同じ値を含む場合は異なる並びだけ
+        let mut items = [1, 1, 2, 2];
+        let mut count = 1;
+        while next_permutation(&mut items) {
+            count += 1;
+        }
+        assert_eq!(count, 6);
+
+        let mut empty: [u32; 0] = [];
+        assert!(!next_permutation(&mut empty));
+        assert!(!prev_permutation(&mut [1]));
+
+        // 1からnまでを使うn桁のパンデジタル素数で最大のもの
+        let largest = (1..=9).rev().find_map(|n| {
+            let mut digits = (1..=n).rev().collect::<Vec<u64>>();
+            loop {
+                let x = digits.iter().fold(0, |acc, &d| acc * 10 + d);
+                if is_prime(x) {
+                    return Some(x);
+                }
+                if !prev_permutation(&mut digits) {
+                    return None;
+                }
+            }
+        });
+        assert_eq!(largest, Some(7_652_413));
+    }
+
+    #[test]
+    fn test_permutations() {
+        let actual = permutations(&['a', 'b', 'c'], 2).collect::<Vec<_>>();
+        let expected = vec![['a', 'b'], ['a', 'c'], ['b', 'a'], ['b', 'c'], ['c', 'a'], ['c', 'b']];
+        assert_eq!(actual, expected);
+        assert_eq!(permutations(&[1, 2, 3], 3).count(), 6);
+        assert_eq!(permutations(&[1, 2, 3], 0).collect::<Vec<_>>(), vec![Vec::<i32>::new()]);
+        assert_eq!(permutations(&[1, 2, 3], 4).count(), 0);
+        // 位置が違えば同じ値でも区別する
+        assert_eq!(permutations(&[1, 1, 2], 3).count(), 6);
+        for n in 0..=7 {
+            for k in 0..=n {
+                let expected = (n - k + 1..=n).product::<usize>();
+                let all = permutations(&(0..n).collect::<Vec<_>>(), k).collect::<Vec<_>>();
+                assert_eq!(all.len(), expected);
+                // 辞書順に並んでいる
+                assert!(all.windows(2).all(|w| w[0] < w[1]));
+            }
+        }
+    }
+
+    #[test]
+    fn test_multiset_permutations() {
+        let actual = multiset_permutations(&[2, 1, 1]).collect::<Vec<_>>();
+        assert_eq!(actual, vec![[1, 1, 2], [1, 2, 1], [2, 1, 1]]);
+        // MISSISSIPPI: 11! / (4! 4! 2!)
+        let word = "MISSISSIPPI".chars().collect::<Vec<_>>();
+        assert_eq!(multiset_permutations(&word).count(), 34650);
+        assert_eq!(multiset_permutations::<u8>(&[]).count(), 1);
+    }
+
+    #[test]
+    fn test_combinations() {
+        let actual = combinations(&[1, 2, 3, 4], 2).collect::<Vec<_>>();
+        assert_eq!(actual, vec![[1, 2], [1, 3], [1, 4], [2, 3], [2, 4], [3, 4]]);
+        assert_eq!(combinations(&[1, 2, 3], 0).collect::<Vec<_>>(), vec![Vec::<i32>::new()]);
+        assert_eq!(combinations(&[1, 2, 3], 3).collect::<Vec<_>>(), vec![[1, 2, 3]]);
+        assert_eq!(combinations(&[1, 2, 3], 4).count(), 0);
+        for n in 0..=10 {
+            for k in 0..=n {
+                let binomial = (0..k).fold(1, |acc, i| acc * (n - i) / (i + 1));
+                assert_eq!(combinations(&(0..n).collect::<Vec<_>>(), k).count(), binomial);
+            }
+        }
+    }
+
+    #[test]
+    fn test_nth_permutation_and_rank() {
+        // 0から9までの順列のうち辞書順で100万番目
+        let digits = (0..10).collect::<Vec<u64>>();
+        let millionth = nth_permutation(&digits, 999_999).unwrap();
+        assert_eq!(millionth, vec![2, 7, 8, 3, 9, 1, 5, 4, 6, 0]);
+        assert_eq!(permutation_rank(&millionth), 999_999);
+
+        let items = [1, 2, 3, 4, 5];
+        let mut perm = items;
+        for index in 0..120 {
+            assert_eq!(nth_permutation(&items, index).unwrap(), perm);
+            assert_eq!(permutation_rank(&perm), index);
+            next_permutation(&mut perm);
+        }
+        assert_eq!(nth_permutation(&items, 120), None);
+        assert_eq!(nth_permutation::<u8>(&[], 0), Some(vec![]));
+        assert_eq!(nth_permutation::<u8>(&[], 1), None);
+
+        let items = (0..20).collect::<Vec<_>>();
+        let last = nth_permutation(&items, 2_432_902_008_176_640_000 - 1).unwrap();
+        assert_eq!(last, items.iter().rev().copied().collect::<Vec<_>>());
+        assert_eq!(permutation_rank(&last), 2_432_902_008_176_640_000 - 1);
+        let items = (0..25).collect::<Vec<_>>();
+        assert_eq!(nth_permutation(&items, u64::MAX).unwrap().len(), 25);
+    }
+}
@@ -1,129 +1,670 @@
+use crate::montgomery::{Montgomery128, Montgomery64};
+use crate::quadratic_residue::jacobi_u128;
 use crate::utils;
+use std::ops::{Deref, Range};
+use std::sync::{Arc, OnceLock, RwLock};
 
+pub use crate::certificate::{certify, Certificate};
+
+// 2から順に並んだ素数の表
+// クローンしても中身は共有され、足りなくなったら区間篩で延ばす。
+// 延ばすときは新しい列を作って差し替えるので、読み出し中のスナップショットはそのまま使える。
+#[derive(Clone)]
+pub struct PrimeTable {
+    primes: Arc<RwLock<Arc<Vec<u64>>>>,
+}
+
+impl PrimeTable {
+    const SEGMENT_SIZE: u64 = 1 << 18;
+
+    pub fn new() -> Self {
+        Self::from_primes(vec![2, 3, 5, 7])
+    }
+
+    // プロセス全体で共有する表
+    pub fn global() -> Self {
+        static GLOBAL: OnceLock<PrimeTable> = OnceLock::new();
+        GLOBAL.get_or_init(PrimeTable::new).clone()
+    }
+
+    // 2から順に並んだ素数の列(キャッシュなど)から作る
+    pub fn from_primes(primes: Vec<u64>) -> Self {
+        // 延ばすときに√high以下の素数が揃っているよう、3までは必要
+        let primes = match primes.last() {
+            Some(&last) if last >= 3 => primes,
+            _ => vec![2, 3],
+        };
+        Self { primes: Arc::new(RwLock::new(Arc::new(primes))) }
+    }
+
+    fn snapshot(&self) -> Arc<Vec<u64>> {
+        self.primes.read().unwrap().clone()
+    }
+
+    // doneを満たすまで表を延ばし、そのスナップショットを返す
+    // 列の複製が何度も起きないよう、延ばすときは値の範囲を少なくとも倍にする
+    fn extend_until<F: Fn(&[u64]) -> bool>(&self, done: F) -> Arc<Vec<u64>> {
+        let snapshot = self.snapshot();
+        if done(&snapshot) {
+            return snapshot;
+        }
+        let mut guard = self.primes.write().unwrap();
+        // ロックを待つ間に他のスレッドが延ばしているかもしれない
+        if done(&guard) {
+            return guard.clone();
+        }
+        let mut primes = guard.to_vec();
+        let target = 2 * primes.last().unwrap();
+        let mut is_composite = Vec::new();
+        let mut found = Vec::new();
+        while !done(&primes) || *primes.last().unwrap() < target {
+            // high <= 2 * last <= last^2なので、篩に使う素数は揃っている
+            let last = *primes.last().unwrap();
+            let low = last + 1;
+            let high = last + last.min(Self::SEGMENT_SIZE);
+            found.clear();
+            sieve_segment(low, high, &primes, &mut is_composite, &mut found);
+            primes.extend_from_slice(&found);
+        }
+        *guard = Arc::new(primes);
+        guard.clone()
+    }
+
+    // k番目の素数 (0始まりで、nth_prime(0) = 2)
+    pub fn nth_prime(&self, k: usize) -> u64 {
+        self.extend_until(|primes| primes.len() > k)[k]
+    }
+
+    // n未満の素数
+    pub fn primes_below(&self, n: u64) -> PrimeSlice {
+        let primes = self.extend_until(|primes| *primes.last().unwrap() >= n);
+        let len = primes.partition_point(|&p| p < n);
+        PrimeSlice { primes, len }
+    }
+
+    // 表を先頭から読むイテレータ
+    pub fn iter(&self) -> PrimeIterator {
+        PrimeIterator {
+            cursor: Cursor::Table {
+                table: self.clone(),
+                primes: self.snapshot(),
+                start: 0,
+                index: 0,
+            },
+        }
+    }
+}
+
+impl Default for PrimeTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+
+// PrimeTable::primes_belowの結果
+// 表のスナップショットを保持するので、その後に表が延びても内容は変わらない
+#[derive(Clone)]
+pub struct PrimeSlice {
+    primes: Arc<Vec<u64>>,
+    len: usize,
+}
+
+impl Deref for PrimeSlice {
+    type Target = [u64];
+
+    fn deref(&self) -> &[u64] {
+        &self.primes[..self.len]
+    }
+}
+
+
+// 素数を昇順に返すイテレータ
+// 普段はPrimeTableを読み進めるカーソルで、表から遠く離れた位置から始めるときだけ
+// 6k ± 1を一つずつis_primeで調べる
+#[derive(Clone)]
 pub struct PrimeIterator {
-    primes: Vec<u64>,
-    index: usize,
-    pseudo_prime: PseudoPrimeIterator,
+    cursor: Cursor,
+}
+
+#[derive(Clone)]
+enum Cursor {
+    Table {
+        table: PrimeTable,
+        primes: Arc<Vec<u64>>,
+        start: usize,
+        index: usize,
+    },
+    Sparse {
+        start: u64,
+        pseudo_prime: PseudoPrimeIterator,
+    },
 }
 
 impl PrimeIterator {
+    // starting_atでこれ以下から始めるときは表を延ばして使う
+    const TABLE_LIMIT: u64 = 1 << 24;
+
     pub fn new() -> Self {
+        PrimeTable::global().iter()
+    }
+
+    // n以上の素数を順に返す
+    pub fn starting_at(n: u64) -> Self {
+        let table = PrimeTable::global();
+        if n > Self::TABLE_LIMIT && n > *table.snapshot().last().unwrap() {
+            return Self {
+                cursor: Cursor::Sparse {
+                    start: n,
+                    pseudo_prime: PseudoPrimeIterator::starting_at(n),
+                },
+            };
+        }
+        let primes = table.extend_until(|primes| *primes.last().unwrap() >= n);
+        let start = primes.partition_point(|&p| p < n);
         Self {
-            primes: vec![2, 3],
-            index: 0,
-            pseudo_prime: PseudoPrimeIterator::new(),
+            cursor: Cursor::Table { table, primes, start, index: start },
         }
     }
 
-    fn add_primes(&mut self, mut n: usize) {
-        self.primes.reserve(self.primes.len() + n);
-        while n > 0 {
-            let pseudo_prime = self.pseudo_prime.next().unwrap();
-            if !is_prime(pseudo_prime) {
-                continue;
-            }
-            self.primes.push(pseudo_prime);
-            n -= 1;
+    // 2から順に並んだ素数の列(キャッシュなど)を引き継ぎ、その続きは逐次求める
+    pub fn with_primes(primes: Vec<u64>) -> Self {
+        PrimeTable::from_primes(primes).iter()
+    }
+
+    // 最初の位置に戻る
+    pub fn reset(&mut self) {
+        match &mut self.cursor {
+            Cursor::Table { start, index, .. } => *index = *start,
+            Cursor::Sparse { start, pseudo_prime } => *pseudo_prime = PseudoPrimeIterator::starting_at(*start),
         }
     }
 }
 
+impl Default for PrimeIterator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Iterator for PrimeIterator {
     type Item = u64;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.index == self.primes.len() {
-            self.add_primes(100);
+        match &mut self.cursor {
+            Cursor::Table { table, primes, index, .. } => {
+                if *index == primes.len() {
+                    let len = *index + 1;
+                    *primes = table.extend_until(|primes| primes.len() >= len);
+                }
+                let prime = primes[*index];
+                *index += 1;
+                Some(prime)
+            }
+            Cursor::Sparse { pseudo_prime, .. } => pseudo_prime.find(|&n| is_prime(n)),
         }
-        let prime = self.primes[self.index];
-        self.index += 1;
-        Some(prime)
     }
 }
 
 
+#[derive(Clone)]
 pub struct PseudoPrimeIterator {
-    val: u64,
+    val: Option<u64>,               // u64に収まらなくなったらNone
     plus_one: bool,
 }
 
 impl PseudoPrimeIterator {
     pub fn new() -> Self {
         Self {
-            val: 5,
+            val: Some(5),
             plus_one: false,
         }
     }
+
+    // n以上で最小の6k ± 1 (5以上) から始める
+    pub fn starting_at(n: u64) -> Self {
+        if n <= 5 {
+            return Self::new();
+        }
+        match n % 6 {
+            0 => Self { val: n.checked_add(1), plus_one: true },
+            1 => Self { val: Some(n), plus_one: true },
+            r => Self { val: n.checked_add(5 - r), plus_one: false },
+        }
+    }
+}
+
+impl Default for PseudoPrimeIterator {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Iterator for PseudoPrimeIterator {
     type Item = u64;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let result = self.val;
-        self.val += if self.plus_one { 4 } else { 2 };
+        let result = self.val?;
+        self.val = result.checked_add(if self.plus_one { 4 } else { 2 });
         self.plus_one = !self.plus_one;
         Some(result)
     }
 }
 
 
-// ミラーラビン素数判定法
-fn miller_rabin_test(n: u64, bases: &[u64]) -> bool {
+// nより大きい最小の素数 (u64に収まらなければNone)
+pub fn next_prime(n: u64) -> Option<u64> {
+    PrimeIterator::starting_at(n.checked_add(1)?).next()
+}
+
+
+// nより小さい最大の素数
+pub fn prev_prime(n: u64) -> Option<u64> {
+    match n {
+        0..=2 => None,
+        3 => Some(2),
+        4 | 5 => Some(3),
+        _ => {
+            // 6k ± 1を降順に調べる
+            let mut candidate = n - 1;
+            while candidate % 6 != 1 && candidate % 6 != 5 {
+                candidate -= 1;
+            }
+            while !is_prime(candidate) {
+                candidate -= if candidate % 6 == 1 { 2 } else { 4 };
+            }
+            Some(candidate)
+        }
+    }
+}
+
+
+// limit以下の素数をエラトステネスの篩で求める
+pub fn sieve_of_eratosthenes(limit: usize) -> Vec<u64> {
+    let mut is_composite = vec![false; limit + 1];
+    let mut primes = Vec::new();
+    for n in 2..=limit {
+        if is_composite[n] {
+            continue;
+        }
+        primes.push(n as u64);
+        for m in (n * n..=limit).step_by(n) {
+            is_composite[m] = true;
+        }
+    }
+    primes
+}
+
+
+// a以上b以下の素数
+// 区間が√bに比べて十分狭ければ一つずつis_primeで調べ、
+// そうでなければ√b以下の素数で区間篩を行う。
+pub fn primes_in_range(a: u64, b: u64) -> Vec<u64> {
+    if a > b {
+        return Vec::new();
+    }
+    let sqrt_b = utils::isqrt(b);
+    if b - a < sqrt_b / 16 {
+        return (a..=b).filter(|&n| is_prime(n)).collect();
+    }
+
+    const SEGMENT_SIZE: u64 = 1 << 16;
+    let base_primes = sieve_of_eratosthenes(sqrt_b as usize);
+    let mut primes = Vec::new();
+    let mut is_composite = Vec::new();
+    let mut low = a.max(2);
+    while low <= b {
+        let high = b.min(low.saturating_add(SEGMENT_SIZE - 1));
+        sieve_segment(low, high, &base_primes, &mut is_composite, &mut primes);
+        if high == u64::MAX {
+            break;
+        }
+        low = high + 1;
+    }
+    primes
+}
+
+
+// 区間篩の一区間分
+// low以上high以下の素数をprimesに追加する (2 <= low)
+// base_primesには√high以下の素数がすべて昇順に含まれていること
+// is_compositeは作業領域で、呼び出しをまたいで使い回せる
+pub fn sieve_segment(low: u64, high: u64, base_primes: &[u64], is_composite: &mut Vec<bool>, primes: &mut Vec<u64>) {
+    is_composite.clear();
+    is_composite.resize((high - low + 1) as usize, false);
+    for &p in base_primes.iter() {
+        if p * p > high {
+            break;
+        }
+        // low以上で最初のpの倍数 (ただしp自身は除く)
+        let first = match low.div_ceil(p).checked_mul(p) {
+            Some(first) => first.max(p * p),
+            None => continue,
+        };
+        for i in ((first - low) as usize..is_composite.len()).step_by(p as usize) {
+            is_composite[i] = true;
+        }
+    }
+    primes.extend(
+        is_composite.iter()
+            .enumerate()
+            .filter(|&(_, &composite)| !composite)
+            .map(|(i, _)| low + i as u64)
+    );
+}
+
+
+// nが底aに関する強い確率的素数かどうか (nは3以上の奇数)
+// n - 1 = 2^s * tとしたとき
+//   a^t ≡ 1 または a^(2^i * t) ≡ -1 (0 <= i < s)
+// ならばtrue。素数ならば必ずtrueになる。
+pub fn strong_probable_prime(n: u64, a: u64) -> bool {
+    assert!(n >= 3 && n % 2 == 1, "n must be an odd number greater than 2");
+    strong_probable_prime_mont(&Montgomery64::new(n), a)
+}
+
+// 同じnについて何度も調べるときはモンゴメリ表現の準備を使い回す
+fn strong_probable_prime_mont(mont: &Montgomery64, a: u64) -> bool {
+    let n = mont.modulus();
+    if a.is_multiple_of(n) {
+        return true;
+    }
     // n - 1 = 2^s * tとなるようなs, tを求める
     let mut t = n - 1;
     let s = t.trailing_zeros() as u64;
     t >>= s;
 
-    'test: for a in bases {
-        // a^(2^i * t) i: 0..=s-1
-        // の全てがpを法として1と合同であれば
-        let mut x = utils::modpow(*a, t, n) as u128;      // x *= x;でのオーバーフロー防止のためu128にキャスト
-        if x == 1 || x == n as u128 - 1 {
-            continue 'test;
-        }
-        // そうでなければ
-        // その中に-1と合同である数があればYes?
-        for _ in 1..=s-1 {
-            x *= x;
-            x %= n as u128;
-            if x == n as u128 - 1 {
-                continue 'test;
-            }
+    // a^(2^i * t) i: 0..=s-1
+    // の全てがpを法として1と合同であれば
+    let one = mont.one();
+    let minus_one = mont.sub(0, one);
+    let mut x = mont.pow(mont.to_mont(a), t);
+    if x == one || x == minus_one {
+        return true;
+    }
+    // そうでなければ
+    // その中に-1と合同である数があればYes?
+    for _ in 1..=s-1 {
+        x = mont.mul(x, x);
+        if x == minus_one {
+            return true;
         }
-        // そうでなければfalse
-        return false;
     }
-    true
+    // そうでなければfalse
+    false
+}
+
+
+// ミラーラビン素数判定法
+// nが合成数であることを示す底(witness)があればそれを返し、なければNone
+pub fn miller_rabin(n: u64, bases: &[u64]) -> Option<u64> {
+    assert!(n >= 3 && n % 2 == 1, "n must be an odd number greater than 2");
+    let mont = Montgomery64::new(n);
+    bases.iter().copied().find(|&a| !strong_probable_prime_mont(&mont, a))
+}
+
+
+// rangeに含まれる底aに関する強い擬素数 (強い確率的素数である奇数の合成数)
+pub fn strong_pseudoprimes(a: u64, range: Range<u64>) -> Vec<u64> {
+    range
+        .filter(|&n| n >= 3 && n % 2 == 1)
+        .filter(|&n| strong_probable_prime(n, a) && !is_prime(n))
+        .collect()
 }
 
 
+// is_primeで使う底の表
+// (bound, bases): n < boundならばbasesすべてで強い確率的素数であるnは素数
+// boundはそれぞれbasesすべてに関する最小の強い擬素数
+pub const MILLER_RABIN_BASES: &[(u64, &[u64])] = &[
+    (2047, &[2]),
+    (1_373_653, &[2, 3]),
+    (9_080_191, &[31, 73]),
+    (25_326_001, &[2, 3, 5]),
+    (3_215_031_751, &[2, 3, 5, 7]),
+    (4_759_123_141, &[2, 7, 61]),
+    (1_122_004_669_633, &[2, 13, 23, 1662803]),
+    (2_152_302_898_747, &[2, 3, 5, 7, 11]),
+    (3_474_749_660_383, &[2, 3, 5, 7, 11, 13]),
+    (341_550_071_728_321, &[2, 3, 5, 7, 11, 13, 17]),
+    (3_825_123_056_546_413_051, &[2, 3, 5, 7, 11, 13, 17, 19, 23]),
+];
+
+// u64全体で十分な底
+pub const MILLER_RABIN_BASES_U64: &[u64] = &[2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+
+
 pub fn is_prime(n: u64) -> bool {
     match n {
         0 | 1 => false,
         2 | 3 => true,
-        _ if n % 2 == 0 => false,
+        _ if n.is_multiple_of(2) => false,
         _  => {
-            let bases: &[u64] = match n {
-                _ if n < 2047 => &[2],
-                _ if n < 1_373_653 => &[2, 3],
-                _ if n < 9_080_191 => &[31, 73],
-                _ if n < 25_326_001 => &[2, 3, 5],
-                _ if n < 3_215_031_751 => &[2, 3, 5, 7],
-                _ if n < 4_759_123_141 => &[2, 7, 61],
-                _ if n < 1_122_004_669_633 => &[2, 13, 23, 1662803],
-                _ if n < 2_152_382_898_747 => &[2, 3, 5, 7, 11],
-                _ if n < 3_474_749_660_383 => &[2, 3, 5, 7, 11, 13],
-                _ if n < 341_550_071_728_321 => &[2, 3, 5, 7, 11, 13, 17],
-                _ if n < 3_825_123_056_546_413_051 => &[2, 3, 5, 7, 11, 13, 17, 19, 23],
-                _ => &[2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37],
-            };
-            miller_rabin_test(n, bases)
+            let bases = MILLER_RABIN_BASES.iter()
+                .find(|&&(bound, _)| n < bound)
+                .map_or(MILLER_RABIN_BASES_U64, |&(_, bases)| bases);
+            miller_rabin(n, bases).is_none()
         }
     }
 }
 
 
+// u128用の素数判定 (BPSW判定法)
+// 底2のミラーラビン判定と強いリュカ判定の両方を通過した数を素数とする。
+// 反例は見つかっていない。
+pub fn is_prime_u128(n: u128) -> bool {
+    if n <= u64::MAX as u128 {
+        return is_prime(n as u64);
+    }
+    const SMALL_PRIMES: &[u128] = &[2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47];
+    if SMALL_PRIMES.iter().any(|&p| n.is_multiple_of(p)) {
+        return false;
+    }
+    bpsw_test(n)
+}
+
+
+// nは5以上の奇数
+fn bpsw_test(n: u128) -> bool {
+    let mont = Montgomery128::new(n);
+
+    // 底2の強い擬素数判定
+    let mut t = n - 1;
+    let s = t.trailing_zeros();
+    t >>= s;
+    let minus_one = mont.sub(0, mont.one());
+    let mut x = mont.pow(mont.to_mont(2), t);
+    if x != mont.one() && x != minus_one {
+        let mut passed = false;
+        for _ in 1..s {
+            x = mont.mul(x, x);
+            if x == minus_one {
+                passed = true;
+                break;
+            }
+        }
+        if !passed {
+            return false;
+        }
+    }
+
+    // Selfridgeの方法でD = 5, -7, 9, -11, ...のうち(D/n) = -1となる最初のDを選ぶ
+    // nが平方数だとそのようなDは存在しないので、見つからなければ途中で平方数かどうか調べる
+    let mut d: i128 = 5;
+    loop {
+        let d_mod_n = if d < 0 { n - d.unsigned_abs() % n } else { d as u128 };
+        match jacobi_u128(d_mod_n, n) {
+            -1 => break,
+            0 if d.unsigned_abs() != n => return false,
+            _ => {}
+        }
+        if d == 61 && utils::is_square_u128(n) {
+            return false;
+        }
+        d = if d > 0 { -d - 2 } else { -d + 2 };
+    }
+    strong_lucas_test(n, &mont, d)
+}
+
+
+// P = 1, Q = (1 - D) / 4のリュカ数列による強いリュカ判定
+// n + 1 = 2^s * tとして
+//   U_t ≡ 0 または V_(2^r * t) ≡ 0 (0 <= r < s)
+// ならばtrue
+fn strong_lucas_test(n: u128, mont: &Montgomery128, d: i128) -> bool {
+    let to_mont_signed = |x: i128| {
+        let x_mod_n = mont.to_mont(x.unsigned_abs() % n);
+        if x < 0 { mont.sub(0, x_mod_n) } else { x_mod_n }
+    };
+    // (x / 2) mod n
+    let half = |x: u128| if x.is_multiple_of(2) { x / 2 } else { x / 2 + n / 2 + 1 };
+
+    let d_mont = to_mont_signed(d);
+    let q_mont = to_mont_signed((1 - d) / 4);
+
+    // n + 1はオーバーフローしうるので(n + 1) / 2^sを直接求める
+    let s = (n >> 1).trailing_ones() + 1;
+    let t = (n >> s) + 1;
+
+    // 上位ビットから順に
+    //   U_2k = U_k * V_k, V_2k = V_k^2 - 2Q^k
+    //   U_(k+1) = (U_k + V_k) / 2, V_(k+1) = (D * U_k + V_k) / 2
+    // で(U_k, V_k, Q^k)を求める
+    let mut u = mont.one();
+    let mut v = mont.one();
+    let mut q_k = q_mont;
+    for i in (0..127 - t.leading_zeros()).rev() {
+        u = mont.mul(u, v);
+        v = mont.sub(mont.mul(v, v), mont.add(q_k, q_k));
+        q_k = mont.mul(q_k, q_k);
+        if (t >> i) & 1 == 1 {
+            let (new_u, new_v) = (
+                half(mont.add(u, v)),
+                half(mont.add(mont.mul(d_mont, u), v)),
+            );
+            u = new_u;
+            v = new_v;
+            q_k = mont.mul(q_k, q_mont);
+        }
+    }
+
+    if u == 0 || v == 0 {
+        return true;
+    }
+    for _ in 1..s {
+        v = mont.sub(mont.mul(v, v), mont.add(q_k, q_k));
+        if v == 0 {
+            return true;
+        }
+        q_k = mont.mul(q_k, q_k);
+    }
+    false
+}
+
+
+// 素因数分解
+// 戻り値は(素因数, 指数)の組を素因数の昇順に並べたもの
+// 小さな素因数は試し割りで取り除き、残りはポラードのρ法で分解する
+pub fn factorize(mut n: u64) -> Vec<(u64, u32)> {
+    const TRIAL_DIVISION_LIMIT: u64 = 1 << 10;
+
+    assert_ne!(n, 0);
+    let mut factors = Vec::new();
+    let mut divide_out = |n: &mut u64, p: u64| {
+        let mut e = 0;
+        while n.is_multiple_of(p) {
+            *n /= p;
+            e += 1;
+        }
+        if e > 0 {
+            factors.push((p, e));
+        }
+    };
+    divide_out(&mut n, 2);
+    divide_out(&mut n, 3);
+    for p in PseudoPrimeIterator::new() {
+        if p > n / p || p >= TRIAL_DIVISION_LIMIT {
+            break;
+        }
+        divide_out(&mut n, p);
+    }
+    // 残りの素因数はすべてTRIAL_DIVISION_LIMIT以上
+    let mut large_primes = Vec::new();
+    collect_prime_factors(n, &mut large_primes);
+    large_primes.sort_unstable();
+    large_primes.dedup();
+    for p in large_primes {
+        divide_out(&mut n, p);
+    }
+    factors
+}
+
+// nの素因数を(重複を許して)primesに加える (nは2と3を素因数に持たない)
+fn collect_prime_factors(n: u64, primes: &mut Vec<u64>) {
+    if n == 1 {
+        return;
+    }
+    if is_prime(n) {
+        primes.push(n);
+        return;
+    }
+    let d = pollard_rho(n);
+    collect_prime_factors(d, primes);
+    collect_prime_factors(n / d, primes);
+}
+
+// 奇数の合成数nの自明でない約数を一つ返す (ポラードのρ法、Brentの変種)
+// x -> x^2 + cで作った列が法pで一周するとgcd(x - y, n)にpが現れる。
+// gcdはBATCH回分の差の積についてまとめて取り、nになってしまったら一つずつやり直す。
+fn pollard_rho(n: u64) -> u64 {
+    const BATCH: u64 = 128;
+
+    let mont = Montgomery64::new(n);
+    let mut c = 1;
+    loop {
+        let c_mont = mont.to_mont(c);
+        let f = |x: u64| mont.add(mont.mul(x, x), c_mont);
+        let mut y = mont.to_mont(2);
+        let mut x = y;
+        let mut saved_y = y;
+        let mut product = mont.one();
+        let mut g = 1;
+        let mut r = 1;
+        while g == 1 {
+            x = y;
+            for _ in 0..r {
+                y = f(y);
+            }
+            let mut k = 0;
+            while k < r && g == 1 {
+                saved_y = y;
+                for _ in 0..BATCH.min(r - k) {
+                    y = f(y);
+                    product = mont.mul(product, mont.sub(x, y));
+                }
+                // モンゴメリ表現はRが奇数なのでgcdを変えない
+                g = utils::gcd(product, n);
+                k += BATCH;
+            }
+            r *= 2;
+        }
+        if g == n {
+            loop {
+                saved_y = f(saved_y);
+                g = utils::gcd(mont.sub(x, saved_y), n);
+                if g != 1 {
+                    break;
+                }
+            }
+        }
+        if g != n {
+            return g;
+        }
+        c += 1;
+    }
+}
+
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -141,6 +682,186 @@ mod tests {
         assert!(x % 6 == 1);
     }
 
+    #[test]
+    fn test_pseudo_prime_starting_at() {
+        for n in 0..100 {
+            let expected = PseudoPrimeIterator::new().skip_while(|&x| x < n).take(10).collect::<Vec<_>>();
+            let actual = PseudoPrimeIterator::starting_at(n).take(10).collect::<Vec<_>>();
+            assert_eq!(actual, expected);
+        }
+    }
+
+    #[test]
+    fn test_starting_at() {
+        for n in 0..200 {
+            let expected = PrimeIterator::new().skip_while(|&p| p < n).take(20).collect::<Vec<_>>();
+            let actual = PrimeIterator::starting_at(n).take(20).collect::<Vec<_>>();
+            assert_eq!(actual, expected);
+        }
+        let actual = PrimeIterator::starting_at(1_000_000_000_000).take(3).collect::<Vec<_>>();
+        assert_eq!(actual, vec![1_000_000_000_039, 1_000_000_000_061, 1_000_000_000_063]);
+    }
+
+    #[test]
+    fn test_prime_table() {
+        let table = PrimeTable::new();
+        assert_eq!(table.nth_prime(0), 2);
+        assert_eq!(table.nth_prime(10), 31);
+        assert_eq!(table.nth_prime(10000), 104_743);
+        assert_eq!(table.primes_below(2).len(), 0);
+        assert_eq!(&*table.primes_below(3), &[2]);
+        assert_eq!(&*table.primes_below(30), &[2, 3, 5, 7, 11, 13, 17, 19, 23, 29]);
+        assert_eq!(table.primes_below(1_000_000).len(), 78498);
+        assert_eq!(&*table.primes_below(10_000), &sieve_of_eratosthenes(10_000)[..]);
+
+        // クローンは表を共有する
+        let shared = table.clone();
+        let before = table.snapshot().len();
+        shared.nth_prime(2 * before);
+        assert!(table.snapshot().len() > 2 * before);
+
+        // 複数スレッドから同時に延ばしても同じ表になる
+        let table = PrimeTable::from_primes(vec![2, 3]);
+        let expected = sieve_of_eratosthenes(2_000_000);
+        std::thread::scope(|scope| {
+            for i in 0..4 {
+                let table = table.clone();
+                let expected = &expected;
+                scope.spawn(move || {
+                    let k = 100_000 + 10_000 * i;
+                    assert_eq!(table.nth_prime(k), expected[k]);
+                    assert_eq!(table.iter().take(k).collect::<Vec<_>>(), expected[..k]);
+                });
+            }
+        });
+    }
+
+    #[test]
+    fn test_prime_iterator_clone_reset() {
+        let mut primes = PrimeIterator::new();
+        assert_eq!(primes.by_ref().take(5).collect::<Vec<_>>(), vec![2, 3, 5, 7, 11]);
+        let mut cloned = primes.clone();
+        assert_eq!(primes.next(), Some(13));
+        assert_eq!(cloned.next(), Some(13));
+        primes.reset();
+        assert_eq!(primes.take(3).collect::<Vec<_>>(), vec![2, 3, 5]);
+
+        let mut primes = PrimeIterator::starting_at(100);
+        primes.nth(1000);
+        primes.reset();
+        assert_eq!(primes.next(), Some(101));
+
+        let mut primes = PrimeIterator::starting_at(1_000_000_000_000);
+        primes.nth(3);
+        primes.reset();
+        assert_eq!(primes.next(), Some(1_000_000_000_039));
+
+        let mut primes = PrimeIterator::with_primes(vec![2, 3, 5]);
+        assert_eq!(primes.nth(1000), PrimeIterator::new().nth(1000));
+        primes.reset();
+        assert_eq!(primes.next(), Some(2));
+    }
+
+    #[test]
+    fn test_next_prev_prime() {
+        let primes = PrimeIterator::new().take(1000).collect::<Vec<_>>();
+        for n in 0..*primes.last().unwrap() {
+            let next = *primes.iter().find(|&&p| p > n).unwrap();
+            assert_eq!(next_prime(n), Some(next));
+            let prev = primes.iter().rev().find(|&&p| p < n).copied();
+            assert_eq!(prev_prime(n), prev);
+        }
+        assert_eq!(next_prime(1_000_000_000_000), Some(1_000_000_000_039));
+        // u64で最大の素数の前後
+        assert_eq!(next_prime(u64::MAX - 100), Some(18_446_744_073_709_551_521));
+        assert_eq!(next_prime(18_446_744_073_709_551_556), Some(18_446_744_073_709_551_557));
+        assert_eq!(next_prime(18_446_744_073_709_551_557), None);
+        assert_eq!(next_prime(u64::MAX - 1), None);
+        assert_eq!(next_prime(u64::MAX), None);
+        assert_eq!(PrimeIterator::starting_at(u64::MAX - 1).next(), None);
+        assert_eq!(PseudoPrimeIterator::starting_at(u64::MAX - 10).collect::<Vec<_>>(), vec![u64::MAX - 10, u64::MAX - 8, u64::MAX - 4, u64::MAX - 2]);
+        assert_eq!(prev_prime(1_000_000_000_000), Some(999_999_999_989));
+        assert_eq!(prev_prime(u64::MAX), Some(18_446_744_073_709_551_557));
+    }
+
+    #[test]
+    fn test_primes_in_range() {
+        let primes = PrimeIterator::new().take_while(|&p| p <= 100_000).collect::<Vec<_>>();
+        assert_eq!(sieve_of_eratosthenes(100_000), primes);
+        assert_eq!(primes_in_range(0, 100_000), primes);
+        for &(a, b) in &[(0, 0), (0, 1), (2, 2), (3, 10), (90, 97), (98, 100), (1000, 5000), (20000, 99999)] {
+            let expected = primes.iter().copied().filter(|&p| a <= p && p <= b).collect::<Vec<_>>();
+            assert_eq!(primes_in_range(a, b), expected);
+        }
+        assert!(primes_in_range(10, 9).is_empty());
+
+        // 区間篩と一つずつの判定で結果が一致する
+        let a = 1_000_000_000_000;
+        let wide = primes_in_range(a, a + 100_000);
+        assert_eq!(wide, (a..=a + 100_000).filter(|&n| is_prime(n)).collect::<Vec<_>>());
+        let narrow = primes_in_range(a, a + 1000);
+        assert_eq!(&narrow[..], &wide[..narrow.len()]);
+        let expected = vec![18_446_744_073_709_551_521, 18_446_744_073_709_551_533, 18_446_744_073_709_551_557];
+        assert_eq!(primes_in_range(u64::MAX - 100, u64::MAX), expected);
+    }
+
+    #[test]
+    fn test_strong_probable_prime() {
+        for n in (3..10000).step_by(2) {
+            if is_prime(n) {
+                assert!((1..100).all(|a| strong_probable_prime(n, a)));
+            }
+        }
+        assert!(strong_probable_prime(2047, 2));
+        assert!(!strong_probable_prime(2047, 3));
+        assert!(strong_probable_prime(7, 14));
+        assert_eq!(miller_rabin(2047, &[2, 3, 5]), Some(3));
+        assert_eq!(miller_rabin(1_373_653, &[2, 3, 5]), Some(5));
+        assert_eq!(miller_rabin(1_000_000_007, &[2, 3, 5]), None);
+        assert_eq!(miller_rabin(561, &[2]), Some(2));
+    }
+
+    #[test]
+    fn test_strong_pseudoprimes() {
+        let expected = vec![2047, 3277, 4033, 4681, 8321, 15841, 29341, 42799, 49141, 52633, 65281, 74665, 80581, 85489, 88357, 90751];
+        assert_eq!(strong_pseudoprimes(2, 0..100000), expected);
+        assert_eq!(strong_pseudoprimes(3, 0..10000), vec![121, 703, 1891, 3281, 8401, 8911]);
+    }
+
+    // 表の各boundが実際にbasesすべてに関する強い擬素数(つまり表の値を大きく取りすぎていない)であることと、
+    // 篩で求めた素数表と照らしてbound未満に反例がないことを確かめる
+    // (後者は25_326_001以下の行のみ。それより大きい行はbound未満に反例がないことを網羅的には確かめておらず、
+    // boundが下に挙げた文献の強い擬素数の値と一致することを確かめるだけ)
+    #[test]
+    fn test_miller_rabin_bases() {
+        // 篩で確かめられない行は次の文献の値による
+        //   3_215_031_751:                     Pomerance, Selfridge, Wagstaff, "The pseudoprimes to 25·10^9", Math. Comp. 35 (1980)
+        //   4_759_123_141 〜 341_550_071_728_321: Jaeschke, "On strong pseudoprimes to several bases", Math. Comp. 61 (1993)
+        //   3_825_123_056_546_413_051:         Jiang, Deng, "Strong pseudoprimes to the first eight prime bases", Math. Comp. 83 (2014)
+        //   MILLER_RABIN_BASES_U64:            Sorenson, Webster, "Strong pseudoprimes to twelve prime bases", Math. Comp. 86 (2017)
+        // ここでは各boundがその底すべてに関する強い擬素数であることだけを確かめる
+        for &(bound, bases) in MILLER_RABIN_BASES {
+            assert!(bound % 2 == 1 && bases.iter().all(|&a| strong_probable_prime(bound, a)), "{}", bound);
+            assert!(factorize(bound).len() > 1);
+        }
+
+        // 25_326_001までの行は篩で求めた合成数すべてについて確かめる
+        const SIEVE_LIMIT: usize = 25_326_001;
+        let mut is_composite = vec![false; SIEVE_LIMIT];
+        for p in sieve_of_eratosthenes(utils::isqrt(SIEVE_LIMIT as u64) as usize) {
+            for m in (p * p..SIEVE_LIMIT as u64).step_by(p as usize) {
+                is_composite[m as usize] = true;
+            }
+        }
+        for &(bound, bases) in MILLER_RABIN_BASES.iter().take_while(|&&(bound, _)| bound as usize <= SIEVE_LIMIT) {
+            // 素数は必ず強い確率的素数なので合成数だけ調べればよい
+            for n in (3..bound).step_by(2).filter(|&n| is_composite[n as usize]) {
+                assert!(miller_rabin(n, bases).is_some(), "{} {:?}", n, bases);
+            }
+        }
+        assert!(!is_prime(2_152_302_898_747));
+    }
+
     #[test]
     fn test_is_prime() {
         let primes = &[2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37, 41, 43, 47, 53, 59, 61, 8191];
@@ -151,4 +872,84 @@ mod tests {
 
         assert_eq!((1..=100000).filter(|&x| is_prime(x)).count(), 9592);
     }
+
+    #[test]
+    fn test_bpsw_test() {
+        for n in (5..=100000).step_by(2) {
+            assert_eq!(bpsw_test(n as u128), is_prime(n), "{}", n);
+        }
+        // 底2の強い擬素数
+        for &n in &[2047, 3277, 4033, 4681, 8321, 15841, 29341, 42799, 49141, 52633, 3215031751] {
+            assert!(!bpsw_test(n));
+        }
+        // 強いリュカ擬素数
+        for &n in &[5459, 5777, 10877, 16109, 18971, 22499, 24569, 25199, 40309, 58519] {
+            assert!(!bpsw_test(n));
+        }
+    }
+
+    #[test]
+    fn test_is_prime_u128() {
+        let primes: &[u128] = &[
+            2,
+            (1 << 61) - 1,
+            18_446_744_073_709_551_557,                 // u64最大の素数
+            18_446_744_073_709_551_629,                 // 2^64より大きい最小の素数
+            10_000_000_000_000_000_000_000_013,
+            1_267_650_600_228_229_401_496_703_205_653,
+            (1 << 89) - 1,
+            (1 << 107) - 1,
+            (1 << 127) - 1,
+        ];
+        assert!(primes.iter().all(|&x| is_prime_u128(x)));
+
+        let composites: &[u128] = &[
+            1,
+            (1 << 67) - 1,
+            18_446_744_073_709_551_629 * 3,
+            18_446_744_073_709_551_557 * 18_446_744_073_709_551_557,
+            9_223_372_036_854_775_837 * 18_446_744_073_709_551_557,
+            (1 << 127) + 1,
+            u128::MAX,
+        ];
+        assert!(composites.iter().all(|&x| !is_prime_u128(x)));
+    }
+
+    #[test]
+    fn test_factorize() {
+        let test_cases: &[(u64, &[(u64, u32)])] = &[
+            (1, &[]),
+            (2, &[(2, 1)]),
+            (12, &[(2, 2), (3, 1)]),
+            (97, &[(97, 1)]),
+            (360, &[(2, 3), (3, 2), (5, 1)]),
+            (600851475143, &[(71, 1), (839, 1), (1471, 1), (6857, 1)]),
+            (1 << 40, &[(2, 40)]),
+            (999_999_000_001 * 3, &[(3, 1), (999_999_000_001, 1)]),
+            // 試し割りでは時間がかかる大きな素因数
+            (4_294_967_291 * 4_294_967_279, &[(4_294_967_279, 1), (4_294_967_291, 1)]),
+            (4_294_967_291 * 4_294_967_291, &[(4_294_967_291, 2)]),
+            (u64::MAX, &[(3, 1), (5, 1), (17, 1), (257, 1), (641, 1), (65537, 1), (6_700_417, 1)]),
+            (18_446_744_073_709_551_557, &[(18_446_744_073_709_551_557, 1)]),
+            (2_642_239 * 2_642_239 * 2_642_231, &[(2_642_231, 1), (2_642_239, 2)]),
+            (1_000_003 * 1_000_033 * 1_000_037, &[(1_000_003, 1), (1_000_033, 1), (1_000_037, 1)]),
+            (1031 * 1031 * 1031 * 1033 * 1033, &[(1031, 3), (1033, 2)]),
+        ];
+        for &(n, expected) in test_cases {
+            assert_eq!(&factorize(n), expected);
+        }
+
+        for n in 1..=10000 {
+            let product = factorize(n).iter().map(|&(p, e)| p.pow(e)).product::<u64>();
+            assert_eq!(product, n);
+            assert!(factorize(n).iter().all(|&(p, _)| is_prime(p)));
+        }
+        // 試し割りの範囲を超える素因数を持つ数
+        for n in (1u64 << 40..).step_by(1_000_003).take(300) {
+            let factors = factorize(n);
+            assert_eq!(factors.iter().map(|&(p, e)| p.pow(e)).product::<u64>(), n);
+            assert!(factors.iter().all(|&(p, _)| is_prime(p)));
+            assert!(factors.windows(2).all(|w| w[0].0 < w[1].0));
+        }
+    }
 }
@@ -0,0 +1,187 @@
+use crate::prime::sieve_of_eratosthenes;
+use std::convert::TryInto;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::PathBuf;
+
+
+// 篩った素数をファイルに保存して次回以降の実行で使い回す
+//
+// ファイル形式 (数値はすべてリトルエンディアン)
+//   MAGIC       8byte
+//   VERSION     u32
+//   limit       u64     limit以下の素数を保存している
+//   checksum    u64     limitとビット列のFNV-1aハッシュ
+//   ビット列            i番目のビットが2i + 1の素数判定 (3以上の奇数のみ意味を持つ)
+//
+// 読み込み時にMAGIC、VERSION、長さ、checksumのどれかが合わなければ作り直す。
+pub struct PrimeCache {
+    dir: PathBuf,
+}
+
+impl PrimeCache {
+    const MAGIC: &'static [u8; 8] = b"PEPRIMES";
+    const VERSION: u32 = 1;
+    const FILE_NAME: &'static str = "primes.bin";
+    const HEADER_SIZE: usize = 8 + 4 + 8 + 8;
+
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    pub fn path(&self) -> PathBuf {
+        self.dir.join(Self::FILE_NAME)
+    }
+
+    // limit以下の素数を返す
+    // キャッシュが使えなければ篩い直してキャッシュを書き換える
+    pub fn primes_up_to(&self, limit: u64) -> io::Result<Vec<u64>> {
+        if let Some(primes) = self.load(limit) {
+            return Ok(primes);
+        }
+        self.store(limit)
+    }
+
+    // キャッシュからlimit以下の素数を読み込む
+    // キャッシュがない、壊れている、limitまで届いていないときはNone
+    pub fn load(&self, limit: u64) -> Option<Vec<u64>> {
+        let mut reader = BufReader::new(File::open(self.path()).ok()?);
+        let mut header = [0; Self::HEADER_SIZE];
+        reader.read_exact(&mut header).ok()?;
+        if &header[0..8] != Self::MAGIC {
+            return None;
+        }
+        let version = u32::from_le_bytes(header[8..12].try_into().unwrap());
+        let cached_limit = u64::from_le_bytes(header[12..20].try_into().unwrap());
+        let checksum = u64::from_le_bytes(header[20..28].try_into().unwrap());
+        if version != Self::VERSION || cached_limit < limit {
+            return None;
+        }
+
+        let mut bits = Vec::with_capacity(Self::bitset_len(cached_limit));
+        reader.read_to_end(&mut bits).ok()?;
+        if bits.len() != Self::bitset_len(cached_limit) || Self::checksum(cached_limit, &bits) != checksum {
+            return None;
+        }
+
+        let mut primes = if limit >= 2 { vec![2] } else { Vec::new() };
+        for (i, &byte) in bits.iter().enumerate() {
+            for j in 0..8 {
+                let n = 2 * (8 * i as u64 + j) + 1;
+                if n > limit {
+                    return Some(primes);
+                }
+                if byte >> j & 1 == 1 {
+                    primes.push(n);
+                }
+            }
+        }
+        Some(primes)
+    }
+
+    // limit以下の素数を篩ってキャッシュに書き込み、その素数を返す
+    pub fn store(&self, limit: u64) -> io::Result<Vec<u64>> {
+        let primes = sieve_of_eratosthenes(limit as usize);
+        let mut bits = vec![0u8; Self::bitset_len(limit)];
+        for &p in primes.iter().skip(1) {
+            let i = (p / 2) as usize;
+            bits[i / 8] |= 1 << (i % 8);
+        }
+
+        // 書き込み途中で中断しても壊れたファイルが残らないように一時ファイルから置き換える
+        fs::create_dir_all(&self.dir)?;
+        let tmp_path = self.dir.join(format!("{}.{}.tmp", Self::FILE_NAME, std::process::id()));
+        {
+            let mut writer = BufWriter::new(File::create(&tmp_path)?);
+            writer.write_all(Self::MAGIC)?;
+            writer.write_all(&Self::VERSION.to_le_bytes())?;
+            writer.write_all(&limit.to_le_bytes())?;
+            writer.write_all(&Self::checksum(limit, &bits).to_le_bytes())?;
+            writer.write_all(&bits)?;
+            writer.flush()?;
+        }
+        fs::rename(&tmp_path, self.path())?;
+        Ok(primes)
+    }
+
+    fn bitset_len(limit: u64) -> usize {
+        // limit以下の奇数は⌈limit / 2⌉個
+        limit.div_ceil(2).div_ceil(8) as usize
+    }
+
+    // FNV-1a
+    fn checksum(limit: u64, bits: &[u8]) -> u64 {
+        const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+        const PRIME: u64 = 0x0000_0100_0000_01b3;
+        limit.to_le_bytes()
+            .iter()
+            .chain(bits.iter())
+            .fold(OFFSET_BASIS, |hash, &byte| (hash ^ byte as u64).wrapping_mul(PRIME))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prime::PrimeIterator;
+
+    fn temp_cache(name: &str) -> PrimeCache {
+        let dir = std::env::temp_dir().join(format!("project_euler_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        PrimeCache::new(dir)
+    }
+
+    fn primes_up_to(limit: u64) -> Vec<u64> {
+        PrimeIterator::new().take_while(|&p| p <= limit).collect()
+    }
+
+    #[test]
+    fn test_store_and_load() {
+        let cache = temp_cache("store_and_load");
+        assert_eq!(cache.load(100), None);
+        assert_eq!(cache.primes_up_to(10000).unwrap(), primes_up_to(10000));
+        for &limit in &[0, 1, 2, 3, 100, 9973, 9999, 10000] {
+            assert_eq!(cache.load(limit), Some(primes_up_to(limit)));
+        }
+        // 保存済みの範囲を超えると読み込めない
+        assert_eq!(cache.load(10001), None);
+        assert_eq!(cache.primes_up_to(20000).unwrap(), primes_up_to(20000));
+        assert_eq!(cache.load(20000), Some(primes_up_to(20000)));
+        fs::remove_dir_all(&cache.dir).unwrap();
+    }
+
+    #[test]
+    fn test_prime_iterator_with_cache() {
+        let cache = temp_cache("prime_iterator");
+        let cached = cache.primes_up_to(1000).unwrap();
+        let actual = PrimeIterator::with_primes(cached).take(500).collect::<Vec<_>>();
+        assert_eq!(actual, PrimeIterator::new().take(500).collect::<Vec<_>>());
+        fs::remove_dir_all(&cache.dir).unwrap();
+    }
+
+    #[test]
+    fn test_corruption() {
+        let cache = temp_cache("corruption");
+        cache.store(1000).unwrap();
+        let original = fs::read(cache.path()).unwrap();
+
+        let mut corrupted = original.clone();
+        corrupted[PrimeCache::HEADER_SIZE + 3] ^= 0x10;
+        fs::write(cache.path(), &corrupted).unwrap();
+        assert_eq!(cache.load(1000), None);
+
+        fs::write(cache.path(), &original[..original.len() - 1]).unwrap();
+        assert_eq!(cache.load(1000), None);
+
+        let mut other_version = original.clone();
+        other_version[8] += 1;
+        fs::write(cache.path(), &other_version).unwrap();
+        assert_eq!(cache.load(1000), None);
+
+        // 壊れていれば作り直される
+        assert_eq!(cache.primes_up_to(1000).unwrap(), primes_up_to(1000));
+        assert_eq!(fs::read(cache.path()).unwrap(), original);
+        fs::remove_dir_all(&cache.dir).unwrap();
+    }
+}
@@ -0,0 +1,209 @@
+use crate::prime::{is_prime, sieve_of_eratosthenes, sieve_segment};
+use crate::utils::isqrt;
+use std::collections::VecDeque;
+
+
+// p + offsetがすべて素数となるような素数pを昇順に返すイテレータ
+//
+// [low, low + SEGMENT_SIZE)ごとに[low, low + SEGMENT_SIZE + 最大のoffset)を区間篩で篩い、
+// 区間内の各素数pについてp + offsetがすべて素数か調べる。
+// 篩に使う素数はMAX_BASE_LIMITまでにとどめ、それで篩いきれない大きな区間では
+// 篩に残った候補だけをis_primeで確かめる。
+// p + 最大のoffsetがu64に収まらなくなったところで終わる。
+pub struct PrimeTuples {
+    offsets: Vec<u64>,
+    low: u64,
+    done: bool,
+    base_primes: Vec<u64>,
+    base_limit: u64,
+    found: VecDeque<u64>,
+    is_composite: Vec<bool>,
+    window_primes: Vec<u64>,
+}
+
+impl PrimeTuples {
+    const SEGMENT_SIZE: u64 = 1 << 16;
+    const MAX_BASE_LIMIT: u64 = 1 << 20;
+
+    fn new(offsets: Vec<u64>, start: u64) -> Self {
+        Self {
+            offsets,
+            low: start.max(2),
+            done: false,
+            base_primes: Vec::new(),
+            base_limit: 0,
+            found: VecDeque::new(),
+            is_composite: Vec::new(),
+            window_primes: Vec::new(),
+        }
+    }
+
+    fn sieve_next_segment(&mut self) {
+        let max_offset = *self.offsets.last().unwrap();
+        let last_candidate = u64::MAX - max_offset;
+        let low = self.low;
+        if low > last_candidate {
+            self.done = true;
+            return;
+        }
+        let high = low.saturating_add(Self::SEGMENT_SIZE - 1).min(last_candidate);
+        let window_high = high + max_offset;
+
+        // √window_high以下の素数を用意する (足りなくなったら倍の範囲で作り直す)
+        let needed = (isqrt(window_high) + 1).min(Self::MAX_BASE_LIMIT);
+        if self.base_limit < needed {
+            let limit = needed.max(2 * self.base_limit).min(Self::MAX_BASE_LIMIT);
+            self.base_primes = sieve_of_eratosthenes(limit as usize);
+            self.base_limit = limit;
+        }
+        // 篩いきれていなければ残った数が合成数のこともある
+        let exact = self.base_limit.saturating_mul(self.base_limit) >= window_high;
+
+        self.window_primes.clear();
+        sieve_segment(low, window_high, &self.base_primes, &mut self.is_composite, &mut self.window_primes);
+
+        for &p in self.window_primes.iter().take_while(|&&p| p <= high) {
+            let i = (p - low) as usize;
+            if self.offsets.iter().all(|&offset| !self.is_composite[i + offset as usize])
+                && (exact || self.offsets.iter().all(|&offset| is_prime(p + offset)))
+            {
+                self.found.push_back(p);
+            }
+        }
+        match high.checked_add(1) {
+            Some(next) => self.low = next,
+            None => self.done = true,
+        }
+    }
+}
+
+impl Iterator for PrimeTuples {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.found.is_empty() {
+            if self.done {
+                return None;
+            }
+            self.sieve_next_segment();
+        }
+        self.found.pop_front()
+    }
+}
+
+
+// パターンが許容的か: 要素数k以下のどの素数qについても、offset mod qがすべての剰余類を覆わない
+// 覆ってしまうとp + offsetのどれかは必ずqの倍数になるので、有限個しか見つからない
+pub fn is_admissible(pattern: &[u64]) -> bool {
+    let offsets = normalize(pattern);
+    sieve_of_eratosthenes(offsets.len()).iter().all(|&q| {
+        let mut covered = vec![false; q as usize];
+        for &offset in offsets.iter() {
+            covered[(offset % q) as usize] = true;
+        }
+        covered.contains(&false)
+    })
+}
+
+
+// start以上の素数pで、patternのすべてのoffsetについてp + offsetが素数となるものを返す
+// 許容的でないパターンならNone
+pub fn prime_tuples(pattern: &[u64], start: u64) -> Option<PrimeTuples> {
+    if !is_admissible(pattern) {
+        return None;
+    }
+    Some(PrimeTuples::new(normalize(pattern), start))
+}
+
+
+// p自身も含めるため0を加えて昇順に並べる
+fn normalize(pattern: &[u64]) -> Vec<u64> {
+    let mut offsets = pattern.to_vec();
+    offsets.push(0);
+    offsets.sort_unstable();
+    offsets.dedup();
+    offsets
+}
+
+
+// (p, p + 2)
+pub fn twin_primes(start: u64) -> PrimeTuples {
+    prime_tuples(&[0, 2], start).unwrap()
+}
+
+// (p, p + 4)
+pub fn cousin_primes(start: u64) -> PrimeTuples {
+    prime_tuples(&[0, 4], start).unwrap()
+}
+
+// (p, p + 6)
+pub fn sexy_primes(start: u64) -> PrimeTuples {
+    prime_tuples(&[0, 6], start).unwrap()
+}
+
+// (p, p + 2, p + 6, p + 8)
+pub fn prime_quadruplets(start: u64) -> PrimeTuples {
+    prime_tuples(&[0, 2, 6, 8], start).unwrap()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn brute_force(pattern: &[u64], start: u64, count: usize) -> Vec<u64> {
+        (start..)
+            .filter(|&p| is_prime(p) && pattern.iter().all(|&offset| is_prime(p + offset)))
+            .take(count)
+            .collect()
+    }
+
+    #[test]
+    fn test_is_admissible() {
+        assert!(is_admissible(&[0, 2]));
+        assert!(is_admissible(&[0, 2, 6]));
+        assert!(is_admissible(&[0, 4, 6]));
+        assert!(is_admissible(&[0, 2, 6, 8]));
+        assert!(is_admissible(&[0, 2, 6, 8, 12]));
+        assert!(!is_admissible(&[0, 1]));
+        assert!(!is_admissible(&[0, 2, 4]));
+        assert!(!is_admissible(&[0, 2, 6, 8, 10]));
+        assert!(prime_tuples(&[0, 2, 4], 0).is_none());
+    }
+
+    #[test]
+    fn test_named_tuples() {
+        assert_eq!(twin_primes(0).take(8).collect::<Vec<_>>(), vec![3, 5, 11, 17, 29, 41, 59, 71]);
+        assert_eq!(cousin_primes(0).take(8).collect::<Vec<_>>(), vec![3, 7, 13, 19, 37, 43, 67, 79]);
+        assert_eq!(sexy_primes(0).take(8).collect::<Vec<_>>(), vec![5, 7, 11, 13, 17, 23, 31, 37]);
+        assert_eq!(prime_quadruplets(0).take(6).collect::<Vec<_>>(), vec![5, 11, 101, 191, 821, 1481]);
+        // 10^5以下の双子素数は1224組
+        assert_eq!(twin_primes(0).take_while(|&p| p + 2 <= 100_000).count(), 1224);
+    }
+
+    #[test]
+    fn test_prime_tuples() {
+        let patterns: &[&[u64]] = &[&[2], &[0, 2, 6], &[4, 6], &[2, 6, 8, 12], &[30]];
+        for &pattern in patterns {
+            for &start in &[0, 1000, 10_000_000] {
+                let actual = prime_tuples(pattern, start).unwrap().take(10).collect::<Vec<_>>();
+                assert_eq!(actual, brute_force(pattern, start, 10), "{:?} {}", pattern, start);
+            }
+        }
+        // 篩の素数だけでは篩いきれない範囲
+        let start = 1u64 << 50;
+        let actual = prime_tuples(&[2, 6], start).unwrap().take(5).collect::<Vec<_>>();
+        assert_eq!(actual, brute_force(&[2, 6], start, 5));
+    }
+
+    #[test]
+    fn test_near_u64_max() {
+        // u64::MAX - 58が最大の素数なので、u64::MAXの手前で終わる
+        assert_eq!(twin_primes(u64::MAX - 100).next(), None);
+        assert_eq!(prime_tuples(&[0], u64::MAX - 100).unwrap().collect::<Vec<_>>(), vec![u64::MAX - 94, u64::MAX - 82, u64::MAX - 58]);
+        let start = u64::MAX - 200_000;
+        let expected = (start..=u64::MAX - 2).filter(|&p| is_prime(p) && is_prime(p + 2)).collect::<Vec<_>>();
+        assert_eq!(twin_primes(start).collect::<Vec<_>>(), expected);
+        assert_eq!(prime_tuples(&[u64::MAX - 1], 2).unwrap().next(), None);
+    }
+}
@@ -0,0 +1,315 @@
+use crate::prime::factorize;
+use crate::utils::{addmod, crt, mod_inverse, modpow, mulmod, submod};
+
+
+// ヤコビ記号(a/n) (nは正の奇数)
+// nが素数ならルジャンドル記号に一致する
+pub fn jacobi(a: u64, n: u64) -> i8 {
+    jacobi_u128(a as u128, n as u128)
+}
+
+pub fn jacobi_u128(a: u128, n: u128) -> i8 {
+    assert!(n % 2 == 1, "n must be odd");
+    let mut a = a % n;
+    let mut n = n;
+    let mut result = 1;
+    while a != 0 {
+        // (2/n) = -1 ⇔ n ≡ 3, 5 (mod 8)
+        while a.is_multiple_of(2) {
+            a /= 2;
+            if n % 8 == 3 || n % 8 == 5 {
+                result = -result;
+            }
+        }
+        // 平方剰余の相互法則
+        std::mem::swap(&mut a, &mut n);
+        if a % 4 == 3 && n % 4 == 3 {
+            result = -result;
+        }
+        a %= n;
+    }
+    if n == 1 { result } else { 0 }
+}
+
+
+// ルジャンドル記号(a/p) (pは奇素数)
+pub fn legendre(a: u64, p: u64) -> i8 {
+    jacobi(a, p)
+}
+
+
+// x^2 ≡ a (mod p)となるx (pは素数)
+// 解はxとp - xの二つあり、小さい方を返す
+pub fn sqrt_mod_prime(a: u64, p: u64) -> Option<u64> {
+    let a = a % p;
+    if p == 2 || a == 0 {
+        return Some(a);
+    }
+    if jacobi(a, p) != 1 {
+        return None;
+    }
+    // p - 1 = 2^s * qのsが大きいとTonelli-Shanksは遅くなるのでCipollaを使う
+    let s = (p - 1).trailing_zeros() as u64;
+    let bits = 64 - p.leading_zeros() as u64;
+    let x = if s * (s - 1) > 8 * bits + 20 {
+        cipolla(a, p)
+    } else {
+        tonelli_shanks(a, p)
+    };
+    Some(x.min(p - x))
+}
+
+
+// Tonelli-Shanks (aはpの平方剰余)
+fn tonelli_shanks(a: u64, p: u64) -> u64 {
+    // p - 1 = 2^s * q
+    let s = (p - 1).trailing_zeros();
+    let q = (p - 1) >> s;
+    // 非剰余zを一つ見つける
+    let z = (2..p).find(|&z| jacobi(z, p) == -1).unwrap();
+
+    // x^2 ≡ a * tを保ちながらtの位数を2^mより真に小さくしていき、t = 1になればxが解
+    let mut m = s;
+    let mut c = modpow(z, q, p);            // 位数2^m
+    let mut t = modpow(a, q, p);
+    let mut x = modpow(a, q.div_ceil(2), p);
+    while t != 1 {
+        // t^(2^i) = 1となる最小のi
+        let mut i = 0;
+        let mut t_pow = t;
+        while t_pow != 1 {
+            t_pow = mulmod(t_pow, t_pow, p);
+            i += 1;
+        }
+        let b = modpow(c, 1 << (m - i - 1), p);
+        m = i;
+        c = mulmod(b, b, p);
+        t = mulmod(t, c, p);
+        x = mulmod(x, b, p);
+    }
+    x
+}
+
+
+// Cipolla (aはpの平方剰余)
+// t^2 - aが非剰余となるtをとり、ω^2 = t^2 - aとしたF_p(ω)で(t + ω)^((p+1)/2)を計算する
+fn cipolla(a: u64, p: u64) -> u64 {
+    let (t, w2) = (1..p)
+        .map(|t| (t, submod(mulmod(t, t, p), a, p)))
+        .find(|&(_, w2)| jacobi(w2, p) == -1)
+        .unwrap();
+    // (x0 + x1ω)(y0 + y1ω) = (x0y0 + x1y1ω^2) + (x0y1 + x1y0)ω
+    let mul = |(x0, x1): (u64, u64), (y0, y1): (u64, u64)| {
+        (
+            addmod(mulmod(x0, y0, p), mulmod(mulmod(x1, y1, p), w2, p), p),
+            addmod(mulmod(x0, y1, p), mulmod(x1, y0, p), p),
+        )
+    };
+    let mut result = (1, 0);
+    let mut base = (t, 1);
+    let mut n = p.div_ceil(2);
+    while n != 0 {
+        if n & 1 == 1 {
+            result = mul(result, base);
+        }
+        base = mul(base, base);
+        n >>= 1;
+    }
+    result.0
+}
+
+
+// x^2 ≡ a (mod p^k)となるx (0 <= x < p^k) をすべて昇順に返す (pは素数)
+pub fn sqrt_mod_prime_power(a: u64, p: u64, k: u32) -> Vec<u64> {
+    let m = p.pow(k);
+    let a = a % m;
+    if k == 0 {
+        return vec![0];
+    }
+
+    // a ≡ 0ならx ≡ 0 (mod p^⌈k/2⌉)
+    if a == 0 {
+        let step = p.pow(k.div_ceil(2));
+        return (0..m).step_by(step as usize).collect();
+    }
+
+    // a = p^e * b (pとbは互いに素)ならばeは偶数で、x = p^(e/2) * yとすると
+    // y^2 ≡ b (mod p^(k-e))。yはmod p^(k-e/2)で考えればよいので
+    // mod p^(k-e)の解それぞれについて上位の桁をp^(e/2)通り選べる
+    let mut e = 0;
+    let mut b = a;
+    while b.is_multiple_of(p) {
+        b /= p;
+        e += 1;
+    }
+    if e % 2 == 1 {
+        return Vec::new();
+    }
+    let half = p.pow(e / 2);
+    let unit_modulus = p.pow(k - e);
+    let y_modulus = p.pow(k - e / 2);
+    let mut roots = sqrt_mod_prime_power_unit(b, p, k - e)
+        .iter()
+        .flat_map(|&y0| (y0..y_modulus).step_by(unit_modulus as usize))
+        .map(|y| (y * half) % m)
+        .collect::<Vec<_>>();
+    roots.sort_unstable();
+    roots.dedup();
+    roots
+}
+
+
+// bがpと互いに素なときのx^2 ≡ b (mod p^k)のすべての解
+fn sqrt_mod_prime_power_unit(b: u64, p: u64, k: u32) -> Vec<u64> {
+    let m = p.pow(k);
+    if m == 1 {
+        return vec![0];
+    }
+    if p == 2 {
+        // 奇数の平方は8を法として1と合同
+        return match k {
+            1 => vec![1],
+            2 if b % 4 == 1 => vec![1, 3],
+            2 => Vec::new(),
+            _ if b % 8 != 1 => Vec::new(),
+            _ => {
+                // r^2 ≡ b (mod 2^i)からr^2 ≡ b (mod 2^(i+1))となるrに持ち上げる
+                let mut r = 1;
+                for i in 3..k {
+                    if mulmod(r, r, 1 << (i + 1)) != b % (1 << (i + 1)) {
+                        r += 1 << (i - 1);
+                    }
+                }
+                // 解は±r, ±r + 2^(k-1)
+                let mut roots = vec![r, m - r, (r + m / 2) % m, (m - r + m / 2) % m];
+                roots.sort_unstable();
+                roots.dedup();
+                roots
+            }
+        };
+    }
+
+    // ヘンゼルの補題: r^2 ≡ b (mod p^i)ならば
+    //   r' = r - (r^2 - b) / 2r
+    // はr'^2 ≡ b (mod p^2i)を満たす (1/2rはmod p^kでの逆元)
+    let mut r = match sqrt_mod_prime(b, p) {
+        Some(r) => r,
+        None => return Vec::new(),
+    };
+    let mut precision = 1;
+    while precision < k {
+        let f = submod(mulmod(r, r, m), b % m, m);
+        let inv = mod_inverse(addmod(r, r, m), m).unwrap();
+        r = submod(r, mulmod(f, inv, m), m);
+        precision *= 2;
+    }
+    let mut roots = vec![r.min(m - r), r.max(m - r)];
+    roots.dedup();
+    roots
+}
+
+
+// x^2 ≡ a (mod n)となるx (0 <= x < n) をすべて昇順に返す
+// 各素数冪を法とする解を中国剰余定理で組み合わせる
+pub fn sqrt_mod(a: u64, n: u64) -> Vec<u64> {
+    let mut roots = vec![0];
+    let mut modulus = 1;
+    for (p, k) in factorize(n) {
+        let m = p.pow(k);
+        let prime_power_roots = sqrt_mod_prime_power(a, p, k);
+        // x ≡ r (mod modulus), x ≡ s (mod m)
+        roots = roots.iter()
+            .flat_map(|&r| prime_power_roots.iter().map(move |&s| (r, s)))
+            .map(|(r, s)| crt(&[(r, modulus), (s, m)]).unwrap().0)
+            .collect();
+        modulus *= m;
+    }
+    roots.sort_unstable();
+    roots
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prime::is_prime;
+
+    fn brute_force_sqrt(a: u64, n: u64) -> Vec<u64> {
+        (0..n).filter(|&x| x * x % n == a % n).collect()
+    }
+
+    #[test]
+    fn test_jacobi() {
+        assert_eq!(jacobi(1001, 9907), -1);
+        assert_eq!(jacobi(19, 45), 1);
+        assert_eq!(jacobi(8, 21), -1);
+        assert_eq!(jacobi(5, 21), 1);
+        assert_eq!(jacobi(6, 15), 0);
+        assert_eq!(jacobi(0, 1), 1);
+        for p in (3..200).filter(|&p| is_prime(p)) {
+            for a in 0..p {
+                let expected = match brute_force_sqrt(a, p).len() {
+                    _ if a == 0 => 0,
+                    0 => -1,
+                    _ => 1,
+                };
+                assert_eq!(legendre(a, p), expected);
+                // オイラーの規準
+                let euler = match expected { 0 => 0, 1 => 1, _ => p - 1 };
+                assert_eq!(modpow(a, (p - 1) / 2, p), euler);
+            }
+        }
+        assert_eq!(jacobi_u128(5, (1 << 127) - 1), -1);
+    }
+
+    #[test]
+    fn test_sqrt_mod_prime() {
+        for p in (2..500).filter(|&p| is_prime(p)) {
+            for a in 0..p {
+                let expected = brute_force_sqrt(a, p).first().copied();
+                assert_eq!(sqrt_mod_prime(a, p), expected);
+            }
+        }
+        for &p in &[998_244_353, 1_000_000_007, (1 << 61) - 1, 18_446_744_073_709_551_557] {
+            for a in 1..100 {
+                let x = tonelli_shanks(a * a % p, p);
+                assert!(x == a || x == p - a);
+                let x = cipolla(a * a % p, p);
+                assert!(x == a || x == p - a);
+                assert_eq!(sqrt_mod_prime(a * a % p, p), Some(a));
+            }
+        }
+    }
+
+    #[test]
+    fn test_sqrt_mod_prime_power() {
+        for p in (2..20).filter(|&p| is_prime(p)) {
+            for k in 0..=5 {
+                let m = p.pow(k);
+                if m > 3000 {
+                    break;
+                }
+                for a in 0..m {
+                    assert_eq!(sqrt_mod_prime_power(a, p, k), brute_force_sqrt(a, m), "{} {}^{}", a, p, k);
+                }
+            }
+        }
+        let p = 1_000_000_007;
+        let roots = sqrt_mod_prime_power(2, p, 2);
+        assert_eq!(roots.len(), 2);
+        for x in roots {
+            assert_eq!(mulmod(x, x, p * p), 2);
+        }
+    }
+
+    #[test]
+    fn test_sqrt_mod() {
+        for n in 1..=400 {
+            for a in 0..n {
+                assert_eq!(sqrt_mod(a, n), brute_force_sqrt(a, n), "{} {}", a, n);
+            }
+        }
+        // 1の平方根 mod 3 * 5 * 7 * 11は2^4個
+        assert_eq!(sqrt_mod(1, 1155).len(), 16);
+    }
+}
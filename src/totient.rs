@@ -0,0 +1,103 @@
+use crate::prime::factorize;
+use crate::utils::icbrt;
+
+
+// φ(n) = n∏(1 - 1/p)
+pub fn totient(n: u64) -> u64 {
+    factorize(n).iter().fold(n, |acc, &(p, _)| acc / p * (p - 1))
+}
+
+
+// 0..=limitの各nについてφ(n)を求める (φ(0)は0とする)
+pub fn totient_sieve(limit: usize) -> Vec<u64> {
+    let mut phi = (0..=limit as u64).collect::<Vec<u64>>();
+    for p in 2..=limit {
+        // まだ一度も減らされていなければpは素数
+        if phi[p] != p as u64 {
+            continue;
+        }
+        for m in (p..=limit).step_by(p) {
+            phi[m] -= phi[m] / p as u64;
+        }
+    }
+    phi
+}
+
+
+// Φ(n) = ∑_{k=1}^{n} φ(k)
+//
+// ∑_{d|k} φ(d) = kを1..=nのkについて足し合わせると
+//   ∑_{d=1}^{n} Φ(⌊n/d⌋) = n(n + 1)/2
+// なので
+//   Φ(n) = n(n + 1)/2 - ∑_{d=2}^{n} Φ(⌊n/d⌋)
+// ⌊n/d⌋の値は高々2√n通りしかないので同じ値をとるdをまとめて計算し、
+// n^(2/3)以下は篩で、それより大きい値はメモ化再帰で求める。
+pub fn totient_sum(n: u64) -> u64 {
+    let limit = icbrt(n).pow(2).clamp(1, n.max(1));
+    let mut small = totient_sieve(limit as usize);
+    for i in 1..small.len() {
+        small[i] += small[i - 1];
+    }
+    // ⌊n/v⌋ (v > limit) で添字付けする
+    let mut large = vec![None; (n / limit) as usize + 1];
+    totient_sum_rec(n, n, &small, &mut large)
+}
+
+fn totient_sum_rec(v: u64, n: u64, small: &[u64], large: &mut [Option<u64>]) -> u64 {
+    if (v as usize) < small.len() {
+        return small[v as usize];
+    }
+    let index = (n / v) as usize;
+    if let Some(result) = large[index] {
+        return result;
+    }
+    let mut result = (v as u128 * (v as u128 + 1) / 2) as u64;
+    let mut d = 2;
+    while d <= v {
+        let q = v / d;
+        let d_max = v / q;          // ⌊v/d⌋ == qとなる最大のd
+        result -= (d_max - d + 1) * totient_sum_rec(q, n, small, large);
+        d = d_max + 1;
+    }
+    large[index] = Some(result);
+    result
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::gcd;
+
+    #[test]
+    fn test_totient() {
+        let test_cases = &[(1, 1), (2, 1), (9, 6), (10, 4), (36, 12), (97, 96), (1 << 20, 1 << 19)];
+        for &(n, expected) in test_cases {
+            assert_eq!(totient(n), expected);
+        }
+        for n in 1..=500 {
+            assert_eq!(totient(n), (1..=n).filter(|&k| gcd(n, k) == 1).count() as u64);
+        }
+    }
+
+    #[test]
+    fn test_totient_sieve() {
+        let phi = totient_sieve(10000);
+        assert_eq!(phi[0], 0);
+        for (n, &phi_n) in phi.iter().enumerate().skip(1) {
+            assert_eq!(phi_n, totient(n as u64));
+        }
+    }
+
+    #[test]
+    fn test_totient_sum() {
+        let phi = totient_sieve(3000);
+        let mut sum = 0;
+        for (n, &phi_n) in phi.iter().enumerate().skip(1) {
+            sum += phi_n;
+            assert_eq!(totient_sum(n as u64), sum);
+        }
+        assert_eq!(totient_sum(0), 0);
+        assert_eq!(totient_sum(1_000_000), 303_963_552_392);
+    }
+}
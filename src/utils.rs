@@ -1,36 +1,176 @@
-pub fn gcd(mut a: u64, mut b: u64) -> u64 {
-    while b != 0 {
-        a %= b;
-        std::mem::swap(&mut a, &mut b);
-    }
-    a
+use crate::integer;
+use crate::montgomery::{Barrett64, Montgomery128, Montgomery64};
+
+// u32やu128、BigUIntにはinteger::gcdを使う
+pub fn gcd(a: u64, b: u64) -> u64 {
+    integer::gcd(a, b)
 }
 
 
 pub fn is_palindrome(n: u64) -> bool {
-    let num_digits = (n as f64).log10() as u32 + 1;
-    let mut lt_pow = 10u64.pow(num_digits - 1);
-    let mut rt_pow = 1;
-    for _ in 0..num_digits / 2 {
-        if n / lt_pow % 10 != n / rt_pow % 10 {
-            return false;
+    integer::is_palindrome(n)
+}
+
+
+// ⌊n^(1/k)⌋ (k >= 1)
+// 浮動小数点数で見積もった値から整数のニュートン法で合わせる
+pub fn iroot_u128(n: u128, k: u32) -> u128 {
+    assert!(k >= 1, "k must be positive");
+    if k == 1 || n < 2 {
+        return n;
+    }
+    if k >= 128 {
+        return 1;
+    }
+    // f64の精度は53bitしかないので、大きなnでは見積もりが整数部分でもずれている。
+    // 真の値より大きいところから始めれば、ニュートン法
+    //   x_new = ((k - 1)x + n / x^(k-1)) / k
+    // は⌊n^(1/k)⌋に達するまで単調に減っていく
+    let estimate = (n as f64).powf(1.0 / k as f64) as u128;
+    let mut x = estimate + (estimate >> 32) + 2;
+    loop {
+        let quotient = x.checked_pow(k - 1).map_or(0, |x_pow| n / x_pow);
+        let y = ((k as u128 - 1) * x + quotient) / k as u128;
+        if y >= x {
+            return x;
+        }
+        x = y;
+    }
+}
+
+pub fn iroot(n: u64, k: u32) -> u64 {
+    iroot_u128(n as u128, k) as u64
+}
+
+
+// ⌊√n⌋
+pub fn isqrt(n: u64) -> u64 {
+    iroot(n, 2)
+}
+
+pub fn isqrt_u128(n: u128) -> u128 {
+    iroot_u128(n, 2)
+}
+
+
+// ⌊∛n⌋
+pub fn icbrt(n: u64) -> u64 {
+    iroot(n, 3)
+}
+
+pub fn icbrt_u128(n: u128) -> u128 {
+    iroot_u128(n, 3)
+}
+
+
+pub fn is_square(n: u64) -> bool {
+    let r = isqrt(n);
+    r * r == n
+}
+
+pub fn is_square_u128(n: u128) -> bool {
+    let r = isqrt_u128(n);
+    r * r == n
+}
+
+
+// n = a^k (k >= 2)と表せるか (0と1も含む)
+pub fn is_perfect_power(n: u64) -> bool {
+    is_perfect_power_u128(n as u128)
+}
+
+pub fn is_perfect_power_u128(n: u128) -> bool {
+    if n < 2 {
+        return true;
+    }
+    // a^kが2以上ならk <= log2(n)
+    (2..128 - n.leading_zeros()).any(|k| iroot_u128(n, k).pow(k) == n)
+}
+
+
+// 拡張ユークリッドの互除法
+// ax + by = g (g = gcd(a, b) >= 0)となる(g, x, y)を返す
+pub fn ext_gcd(a: i64, b: i64) -> (i64, i64, i64) {
+    let (g, x, y) = ext_gcd_i128(a as i128, b as i128);
+    (g as i64, x as i64, y as i64)
+}
+
+// 係数の途中計算がu64の範囲の入力でも溢れないようi128で計算する
+fn ext_gcd_i128(a: i128, b: i128) -> (i128, i128, i128) {
+    // (r0, x0, y0), (r1, x1, y1)はどちらも a * x + b * y = r を満たす
+    let (mut r0, mut x0, mut y0) = (a, 1, 0);
+    let (mut r1, mut x1, mut y1) = (b, 0, 1);
+    while r1 != 0 {
+        let q = r0 / r1;
+        (r0, r1) = (r1, r0 - q * r1);
+        (x0, x1) = (x1, x0 - q * x1);
+        (y0, y1) = (y1, y0 - q * y1);
+    }
+    if r0 < 0 { (-r0, -x0, -y0) } else { (r0, x0, y0) }
+}
+
+
+// ax ≡ 1 (mod m)となる0 <= x < m (gcd(a, m) != 1ならNone)
+pub fn mod_inverse(a: u64, m: u64) -> Option<u64> {
+    let (g, x, _) = ext_gcd_i128((a % m) as i128, m as i128);
+    if g != 1 {
+        return None;
+    }
+    Some(x.rem_euclid(m as i128) as u64)
+}
+
+
+// 中国剰余定理: すべての(r, m)についてx ≡ r (mod m)となるxを
+// x ≡ r (mod lcm)の形(0 <= r < lcm)で返す
+// 法は互いに素でなくてよい。矛盾する場合やlcmがu64に収まらない場合はNone
+pub fn crt(congruences: &[(u64, u64)]) -> Option<(u64, u64)> {
+    let mut r = 0u128;
+    let mut m = 1u128;
+    for &(r2, m2) in congruences {
+        assert!(m2 != 0, "modulus must be positive");
+        let (r2, m2) = ((r2 % m2) as u128, m2 as u128);
+        // x = r + m * tとしてm * t ≡ r2 - r (mod m2)を解く
+        let g = gcd(m as u64, m2 as u64) as u128;
+        let diff = (r2 + m2 - r % m2) % m2;
+        if !diff.is_multiple_of(g) {
+            return None;
+        }
+        let m2_g = m2 / g;
+        let inv = mod_inverse((m / g % m2_g) as u64, m2_g as u64)?;
+        let t = diff / g * inv as u128 % m2_g;
+        r += m * t;
+        m *= m2_g;
+        if m > u64::MAX as u128 {
+            return None;
         }
-        lt_pow /= 10;
-        rt_pow *= 10;
     }
-    true
+    Some((r as u64, m as u64))
+}
+
+
+// a * b mod m
+pub fn mulmod(a: u64, b: u64, m: u64) -> u64 {
+    (a as u128 * b as u128 % m as u128) as u64
+}
+
+// a, b < mに対して(a + b) mod m (mが2^63以上でもオーバーフローしない)
+pub fn addmod(a: u64, b: u64, m: u64) -> u64 {
+    if a >= m - b { a - (m - b) } else { a + b }
+}
+
+// a, b < mに対して(a - b) mod m
+pub fn submod(a: u64, b: u64, m: u64) -> u64 {
+    if a >= b { a - b } else { a + (m - b) }
 }
 
 
 pub fn modpow(a: u64, n: u64, m: u64) -> u64 {
-    let a: u128 = a as u128;                // a == u64::MAXの場合のオーバーフロー防止
-    let m: u128 = m as u128;                // aに型を合わせるため
     let a_mod_m = a % m;
     if a_mod_m == 0 || a_mod_m == 1 {       // 0と1はn乗してもそのまま
-        return a_mod_m as u64;
+        return a_mod_m;
     }
     if a_mod_m == m - 1 {                   // -1は偶数乗で1奇数乗で奇数乗で-1
-        return if n % 2 == 0 { 1 } else { a_mod_m as u64 };
+        return if n.is_multiple_of(2) { 1 } else { a_mod_m };
     }
     // n = ∑b_i * 2^i
     // b_i: nの2進数表示のi桁目
@@ -38,22 +178,53 @@ pub fn modpow(a: u64, n: u64, m: u64) -> u64 {
     // a^n mod m = a^(b_0 * 2^0 + b_1 * 2^1 + b_2 * 2^2 + ... + b_k * 2^k)
     //           = a^b_0 * a^(b_1 * 2) * a^(b_2 * 4) * ... * a^(b_k * 2^k)
     // b_iの値は0または1をとるので1のところでa^(2^i)をかけ合わせればよい。
+    // 掛け算の余りはu128の割り算を避けて、mが奇数ならモンゴメリ乗算、偶数ならバレット還元で求める
+    if m % 2 == 1 {
+        let mont = Montgomery64::new(m);
+        return mont.from_mont(mont.pow(mont.to_mont(a_mod_m), n));
+    }
+    Barrett64::new(m).pow(a_mod_m, n)
+}
+
+
+// modpowのu128版
+// mが奇数ならモンゴメリ乗算、偶数なら倍々の足し算で積を求める
+pub fn modpow_u128(a: u128, n: u128, m: u128) -> u128 {
+    if m == 1 {
+        return 0;
+    }
+    if m % 2 == 1 {
+        let mont = Montgomery128::new(m);
+        return mont.from_mont(mont.pow(mont.to_mont(a), n));
+    }
     let mut modpow = 1;
-    let mut a_mod_m = a_mod_m;
+    let mut a_mod_m = a % m;
     let mut n = n;
     while n != 0 {
-        if n & 1 == 1 {                     // ビットが1ならa^(2^i)を蓄積変数にかけ合わせる
-            modpow *= a_mod_m;
-            modpow %= m;
-        }
-        a_mod_m *= a_mod_m;                 // a^1, a^2, a^4, a^8, ...と計算する
-        a_mod_m %= m;
-        if a_mod_m == 1 {
-            return modpow as u64;
+        if n & 1 == 1 {
+            modpow = mulmod_u128(modpow, a_mod_m, m);
         }
+        a_mod_m = mulmod_u128(a_mod_m, a_mod_m, m);
         n >>= 1;
     }
-    modpow as u64
+    modpow
+}
+
+
+// a, b < mに対してa * b mod mを求める
+// b = ∑c_i * 2^iと見てa * 2^iを順に足し合わせる
+pub(crate) fn mulmod_u128(a: u128, mut b: u128, m: u128) -> u128 {
+    let add_mod = |x: u128, y: u128| if x >= m - y { x - (m - y) } else { x + y };
+    let mut result = 0;
+    let mut a = a;
+    while b != 0 {
+        if b & 1 == 1 {
+            result = add_mod(result, a);
+        }
+        a = add_mod(a, a);
+        b >>= 1;
+    }
+    result
 }
 
 
@@ -83,4 +254,166 @@ mod tests {
         assert!(is_palindrome(906609));
         assert!(!is_palindrome(123021));
     }
+
+    #[test]
+    fn test_iroot() {
+        for n in 0..10_000u64 {
+            let expected = (0..=n).take_while(|&x| x * x <= n).last().unwrap();
+            assert_eq!(isqrt(n), expected);
+            assert_eq!(is_square(n), expected * expected == n);
+            let expected = (0..=n).take_while(|&x| x * x * x <= n).last().unwrap();
+            assert_eq!(icbrt(n), expected);
+        }
+        // 浮動小数点数の平方根では誤差が出る値
+        let test_cases = &[
+            (u64::MAX, 4_294_967_295),
+            (4_294_967_295 * 4_294_967_295, 4_294_967_295),
+            (4_294_967_295 * 4_294_967_295 - 1, 4_294_967_294),
+            (999_999_999_999_999_999, 999_999_999),
+            (1_000_000_000_000_000_000, 1_000_000_000),
+            (4_503_599_761_588_224, 67_108_864),
+            (4_503_599_761_588_225, 67_108_865),
+        ];
+        for &(n, expected) in test_cases {
+            assert_eq!(isqrt(n), expected, "{}", n);
+        }
+        for r in (1u64 << 20..(1 << 20) + 1000).chain(u32::MAX as u64 - 1000..=u32::MAX as u64) {
+            assert_eq!(isqrt(r * r), r);
+            assert_eq!(isqrt(r * r - 1), r - 1);
+            assert!(is_square(r * r) && !is_square(r * r + 1) && !is_square(r * r - 1));
+        }
+        for r in 2_000_000u64..2_000_100 {
+            assert_eq!(icbrt(r * r * r), r);
+            assert_eq!(icbrt(r * r * r - 1), r - 1);
+        }
+        assert_eq!(icbrt(u64::MAX), 2_642_245);
+        assert_eq!(iroot(u64::MAX, 1), u64::MAX);
+        assert_eq!(iroot(u64::MAX, 63), 2);
+        assert_eq!(iroot(u64::MAX, 64), 1);
+        assert_eq!(iroot(u64::MAX, 100), 1);
+        assert_eq!(iroot(3u64.pow(40), 40), 3);
+        assert_eq!(iroot(3u64.pow(40) - 1, 40), 2);
+        assert_eq!(iroot(10u64.pow(18), 6), 1000);
+        assert_eq!(iroot(10u64.pow(18) - 1, 6), 999);
+
+        assert_eq!(isqrt_u128(u128::MAX), u64::MAX as u128);
+        assert_eq!(icbrt_u128(u128::MAX), 6_981_463_658_331);
+        for r in (u64::MAX as u128 - 1000..=u64::MAX as u128).chain(1 << 60..(1 << 60) + 1000) {
+            assert_eq!(isqrt_u128(r * r), r);
+            assert_eq!(isqrt_u128(r * r - 1), r - 1);
+            assert!(is_square_u128(r * r) && !is_square_u128(r * r - 1));
+        }
+        assert_eq!(iroot_u128(7u128.pow(45), 45), 7);
+        assert_eq!(iroot_u128(7u128.pow(45) - 1, 45), 6);
+        assert_eq!(iroot_u128(u128::MAX, 127), 2);
+        assert_eq!(iroot_u128(u128::MAX, 128), 1);
+    }
+
+    #[test]
+    fn test_is_perfect_power() {
+        let expected = [0, 1, 4, 8, 9, 16, 25, 27, 32, 36, 49, 64, 81, 100, 121, 125, 128];
+        assert_eq!((0..=128).filter(|&n| is_perfect_power(n)).collect::<Vec<_>>(), expected);
+        assert!(is_perfect_power(3u64.pow(40)));
+        assert!(is_perfect_power(1 << 63));
+        assert!(is_perfect_power(4_294_967_295 * 4_294_967_295));
+        assert!(!is_perfect_power(u64::MAX));
+        assert!(!is_perfect_power(1_000_000_007 * 1_000_000_009));
+        assert!(is_perfect_power_u128(1 << 127));
+        assert!(is_perfect_power_u128(1_000_000_007u128.pow(4)));
+        assert!(!is_perfect_power_u128(u128::MAX));
+    }
+
+    #[test]
+    fn test_ext_gcd() {
+        let test_cases = &[(240, 46, 2), (46, 240, 2), (17, 5, 1), (0, 7, 7), (7, 0, 7), (0, 0, 0), (-12, 18, 6), (12, -18, 6)];
+        for &(a, b, g) in test_cases {
+            let (actual, x, y) = ext_gcd(a, b);
+            assert_eq!(actual, g);
+            assert_eq!(a * x + b * y, g);
+        }
+        let (a, b) = (i64::MAX, 1 << 62);
+        let (g, x, y) = ext_gcd(a, b);
+        assert_eq!(g, 1);
+        assert_eq!(a as i128 * x as i128 + b as i128 * y as i128, 1);
+    }
+
+    #[test]
+    fn test_mod_inverse() {
+        for m in 1..100 {
+            for a in 0..m {
+                let expected = (0..m).find(|&x| a * x % m == 1 % m && gcd(a, m) == 1);
+                assert_eq!(mod_inverse(a, m), expected, "{} {}", a, m);
+            }
+        }
+        let p = 18_446_744_073_709_551_557;
+        let inv = mod_inverse(2, p).unwrap();
+        assert_eq!(modpow(2, p - 2, p), inv);
+        assert_eq!(mod_inverse(u64::MAX, u64::MAX - 1), Some(1));
+        assert_eq!(mod_inverse(6, 1 << 63), None);
+    }
+
+    #[test]
+    fn test_crt() {
+        assert_eq!(crt(&[]), Some((0, 1)));
+        assert_eq!(crt(&[(2, 3), (3, 5), (2, 7)]), Some((23, 105)));
+        assert_eq!(crt(&[(5, 7)]), Some((5, 7)));
+        assert_eq!(crt(&[(12, 7)]), Some((5, 7)));
+        // 互いに素でない法
+        assert_eq!(crt(&[(2, 4), (4, 6)]), Some((10, 12)));
+        assert_eq!(crt(&[(1, 4), (2, 6)]), None);
+        assert_eq!(crt(&[(3, 10), (3, 15), (3, 6)]), Some((3, 30)));
+        for m1 in 1..20 {
+            for m2 in 1..20 {
+                for r1 in 0..m1 {
+                    for r2 in 0..m2 {
+                        let lcm = m1 / gcd(m1, m2) * m2;
+                        let expected = (0..lcm).find(|&x| x % m1 == r1 && x % m2 == r2).map(|x| (x, lcm));
+                        assert_eq!(crt(&[(r1, m1), (r2, m2)]), expected);
+                    }
+                }
+            }
+        }
+        // 途中の積がu64を超える
+        let (p, q) = (4_294_967_291, 4_294_967_279);
+        let (r, m) = crt(&[(p - 1, p), (q - 2, q)]).unwrap();
+        assert_eq!((r % p, r % q, m), (p - 1, q - 2, p * q));
+        assert_eq!(crt(&[(1, 1 << 40), (1, 3_u64.pow(30))]), None);
+    }
+
+    #[test]
+    fn test_mulmod_addmod_submod() {
+        let m = u64::MAX - 58;              // 2^64 - 59 (素数)
+        let test_cases = &[(0, 0), (1, m - 1), (m - 1, m - 1), (m / 2, m / 2 + 1), (12345, 67890)];
+        for &(a, b) in test_cases {
+            let (a128, b128, m128) = (a as u128, b as u128, m as u128);
+            assert_eq!(mulmod(a, b, m) as u128, a128 * b128 % m128);
+            assert_eq!(addmod(a, b, m) as u128, (a128 + b128) % m128);
+            assert_eq!(submod(a, b, m) as u128, (a128 + m128 - b128) % m128);
+        }
+        assert_eq!(mulmod(u64::MAX, u64::MAX, 10), 5);
+        assert_eq!(addmod(0, 0, 1), 0);
+        assert_eq!(submod(3, 5, 7), 5);
+    }
+
+    #[test]
+    fn test_modpow_u128() {
+        for a in 0..30 {
+            for n in 1..20 {
+                for m in 1..50 {
+                    assert_eq!(modpow_u128(a, n, m), modpow(a as u64, n as u64, m as u64) as u128);
+                }
+            }
+        }
+        let m61 = (1u128 << 61) - 1;
+        assert_eq!(modpow_u128(3, m61 - 1, m61), 1);
+
+        let m127 = (1u128 << 127) - 1;
+        assert_eq!(modpow_u128(3, m127 - 1, m127), 1);
+        assert_eq!(modpow_u128(u128::MAX, 2, m127), 1);
+        // 偶数の法
+        assert_eq!(modpow_u128(3, 5, 1 << 100), 243);
+        assert_eq!(modpow_u128((1 << 64) + 1, 2, 1 << 127), (1 << 65) + 1);
+        assert_eq!(modpow_u128(3, 1 << 125, 1 << 127), 1);
+        assert_eq!(modpow_u128(u128::MAX, 3, u128::MAX - 1), 1);
+    }
 }